@@ -4,8 +4,34 @@
 extern crate test;
 use na::RealField;
 use nalgebra as na;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
 use std::f64::consts::PI;
 
+/// Allocator which counts the allocations of the current thread
+struct CountingAllocator;
+
+thread_local! {
+    static NUM_ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = NUM_ALLOCATIONS.try_with(|num| num.set(num.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn num_allocations() -> usize {
+    NUM_ALLOCATIONS.with(|num| num.get())
+}
+
 /*
 ## v0.10.1 on MacBook Pro (Retina, 13-inch, Early 2015, 2.9GHz - 16GB)
 
@@ -47,6 +73,23 @@ test bench_rctree_ik ... bench:       4,394 ns/iter (+/- 73)
 test bench_rctree            ... bench:       1,803 ns/iter (+/- 21)
 test bench_rctree_set_joints ... bench:         204 ns/iter (+/- 17)
 test bench_rctree_ik ... bench:       5,985 ns/iter (+/- 65)
+
+## v0.21.0 on Cloud VM (Intel Xeon Processor, 1 vCPU - 5GB), rustc 1.97.0-nightly
+
+`_rc` uses `RcChain` (`Rc<RefCell>`), the others use `Chain` (`Arc<Mutex>`).
+`update_transforms_into_*` move one joint and recalculate only its subtree.
+`batch_end_poses` calculates 1,000 samples by `Model`, `_by_chain` the same samples by `Chain`.
+
+test bench_rctree                                    ... bench:       2,464 ns/iter (+/- 80)
+test bench_rctree_rc                                 ... bench:         869 ns/iter (+/- 33)
+test bench_rctree_set_joints                         ... bench:         888 ns/iter (+/- 22)
+test bench_rctree_update_transforms_into_shoulder    ... bench:         929 ns/iter (+/- 38)
+test bench_rctree_update_transforms_into_shoulder_rc ... bench:         337 ns/iter (+/- 25)
+test bench_rctree_update_transforms_into_wrist       ... bench:         304 ns/iter (+/- 18)
+test bench_rctree_update_transforms_into_wrist_rc    ... bench:          79 ns/iter (+/- 1)
+test bench_rctree_jacobian_into                      ... bench:         919 ns/iter (+/- 33)
+test bench_rctree_batch_end_poses                    ... bench:      70,012 ns/iter (+/- 2,599)
+test bench_rctree_batch_end_poses_by_chain           ... bench:   2,482,353 ns/iter (+/- 151,556)
 */
 
 fn generate_random_joint_angles_from_limits<T>(limits: &Vec<Option<k::joint::Range<T>>>) -> Vec<T>
@@ -62,9 +105,8 @@ where
         .collect()
 }
 
-#[bench]
-fn bench_rctree(b: &mut test::Bencher) {
-    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+/// Set the positions of all the joints and update the transforms
+fn bench_update_transforms<B: k::NodeBackend<f64>>(b: &mut test::Bencher, chain: k::Chain<f64, B>) {
     let limits = chain.iter_joints().map(|j| j.limits.clone()).collect();
     let angles = generate_random_joint_angles_from_limits(&limits);
     b.iter(|| {
@@ -74,6 +116,18 @@ fn bench_rctree(b: &mut test::Bencher) {
     });
 }
 
+#[bench]
+fn bench_rctree(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    bench_update_transforms(b, chain);
+}
+
+#[bench]
+fn bench_rctree_rc(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    bench_update_transforms(b, chain.to_backend::<k::RcRefCellBackend>());
+}
+
 #[bench]
 fn bench_rctree_set_joints(b: &mut test::Bencher) {
     let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
@@ -83,3 +137,116 @@ fn bench_rctree_set_joints(b: &mut test::Bencher) {
         chain.set_joint_positions(&angles).unwrap();
     });
 }
+
+/// Move `joint_name` and update the transforms, only the moved subtree is recalculated
+fn bench_update_transforms_into_after_moving<B: k::NodeBackend<f64>>(
+    b: &mut test::Bencher,
+    chain: k::Chain<f64, B>,
+    joint_name: &str,
+) {
+    let limits = chain.iter_joints().map(|j| j.limits.clone()).collect();
+    let angles = generate_random_joint_angles_from_limits(&limits);
+    chain.set_joint_positions(&angles).unwrap();
+    let node = chain.find(joint_name).unwrap();
+    let mut transforms = vec![na::Isometry3::identity(); 13];
+    let mut position = 0.1;
+    b.iter(|| {
+        position = -position;
+        node.set_joint_position(position).unwrap();
+        let before = num_allocations();
+        chain.update_transforms_into(&mut transforms).unwrap();
+        assert_eq!(num_allocations(), before);
+    });
+}
+
+// l_shoulder_yaw is the first joint of the left arm, its 6 nodes of 13 are recalculated
+#[bench]
+fn bench_rctree_update_transforms_into_shoulder(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    bench_update_transforms_into_after_moving(b, chain, "l_shoulder_yaw");
+}
+
+#[bench]
+fn bench_rctree_update_transforms_into_shoulder_rc(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    bench_update_transforms_into_after_moving(
+        b,
+        chain.to_backend::<k::RcRefCellBackend>(),
+        "l_shoulder_yaw",
+    );
+}
+
+// l_wrist_pitch is the end of the left arm, only it is recalculated
+#[bench]
+fn bench_rctree_update_transforms_into_wrist(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    bench_update_transforms_into_after_moving(b, chain, "l_wrist_pitch");
+}
+
+#[bench]
+fn bench_rctree_update_transforms_into_wrist_rc(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    bench_update_transforms_into_after_moving(
+        b,
+        chain.to_backend::<k::RcRefCellBackend>(),
+        "l_wrist_pitch",
+    );
+}
+
+#[bench]
+fn bench_rctree_jacobian_into(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    let arm = k::SerialChain::from_end(&chain.find("l_wrist_pitch").unwrap());
+    arm.set_joint_positions(&[0.5, 0.2, 0.0, -0.5, 0.0, -0.3])
+        .unwrap();
+    let mut jacobi = na::DMatrix::zeros(6, arm.active_dof());
+    b.iter(|| {
+        let before = num_allocations();
+        k::jacobian_into(&arm, &mut jacobi).unwrap();
+        assert_eq!(num_allocations(), before);
+    });
+}
+
+const NUM_BATCH_SAMPLES: usize = 1000;
+
+/// Random positions of `NUM_BATCH_SAMPLES` samples, one `Vec` for each sample
+fn generate_batch_samples(chain: &k::Chain<f64>) -> Vec<Vec<f64>> {
+    let limits = chain.iter_joints().map(|j| j.limits.clone()).collect();
+    (0..NUM_BATCH_SAMPLES)
+        .map(|_| generate_random_joint_angles_from_limits(&limits))
+        .collect()
+}
+
+#[bench]
+fn bench_rctree_batch_end_poses(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    let model = k::Model::from_chain(&chain).unwrap();
+    let mut positions = vec![0.0; model.num_positions() * NUM_BATCH_SAMPLES];
+    for (s, angles) in generate_batch_samples(&chain).into_iter().enumerate() {
+        for (i, angle) in angles.into_iter().enumerate() {
+            positions[i * NUM_BATCH_SAMPLES + s] = angle;
+        }
+    }
+    let end = model.find("l_wrist_pitch").unwrap();
+    b.iter(|| {
+        let poses = model
+            .batch_end_poses(end, &positions, NUM_BATCH_SAMPLES)
+            .unwrap();
+        assert_eq!(poses.len(), NUM_BATCH_SAMPLES);
+    });
+}
+
+// The same samples as `bench_rctree_batch_end_poses` by the chain, one by one
+#[bench]
+fn bench_rctree_batch_end_poses_by_chain(b: &mut test::Bencher) {
+    let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    let samples = generate_batch_samples(&chain);
+    let end = chain.find("l_wrist_pitch").unwrap();
+    b.iter(|| {
+        for angles in &samples {
+            chain.set_joint_positions(angles).unwrap();
+            chain.update_transforms();
+            test::black_box(end.world_transform().unwrap());
+        }
+    });
+}
@@ -22,7 +22,7 @@ use kiss3d::event::{Action, Key, WindowEvent};
 use kiss3d::light::Light;
 use kiss3d::scene::SceneNode;
 use kiss3d::window::Window;
-use na::{Isometry3, Point3, Translation3, UnitQuaternion, Vector3};
+use na::{Isometry3, Translation3, UnitQuaternion, Vector3};
 
 fn create_joint_with_link_array() -> k::Node<f32> {
     let fixed: k::Node<f32> = NodeBuilder::new()
@@ -84,13 +84,25 @@ fn create_ground(window: &mut Window) -> Vec<SceneNode> {
                 Translation3::new(size * x_ind, 0.0, size * y_ind),
                 UnitQuaternion::from_euler_angles(0.0, -1.57, -1.57),
             );
-            c0.set_local_transformation(trans);
+            c0.set_local_transformation(to_kiss3d(&trans));
             panels.push(c0);
         }
     }
     panels
 }
 
+// kiss3d depends on another version of nalgebra
+fn to_kiss3d(trans: &Isometry3<f32>) -> kiss3d::nalgebra::Isometry3<f32> {
+    let t = &trans.translation.vector;
+    let q = &trans.rotation.coords;
+    kiss3d::nalgebra::Isometry3::from_parts(
+        kiss3d::nalgebra::Translation3::new(t[0], t[1], t[2]),
+        kiss3d::nalgebra::UnitQuaternion::new_unchecked(kiss3d::nalgebra::Quaternion::new(
+            q[3], q[0], q[1], q[2],
+        )),
+    )
+}
+
 fn create_cubes(window: &mut Window) -> Vec<SceneNode> {
     let mut c_fixed = window.add_cube(0.05, 0.05, 0.05);
     c_fixed.set_color(0.2, 0.2, 0.2);
@@ -130,8 +142,8 @@ fn main() {
     let mut target = end.world_transform().unwrap().clone();
     let mut c_t = window.add_sphere(0.05);
     c_t.set_color(1.0, 0.2, 0.2);
-    let eye = Point3::new(0.5f32, 1.0, 2.0);
-    let at = Point3::new(0.0f32, 0.0, 0.0);
+    let eye = kiss3d::nalgebra::Point3::new(0.5f32, 1.0, 2.0);
+    let at = kiss3d::nalgebra::Point3::new(0.0f32, 0.0, 0.0);
     let mut arc_ball = ArcBall::new(eye, at);
 
     let solver = JacobianIKSolver::default();
@@ -191,9 +203,9 @@ fn main() {
                     println!("Err: {}", err);
                 });
         }
-        c_t.set_local_transformation(target.clone());
+        c_t.set_local_transformation(to_kiss3d(&target));
         for (i, trans) in arm.update_transforms().iter().enumerate() {
-            cubes[i].set_local_transformation(trans.clone());
+            cubes[i].set_local_transformation(to_kiss3d(trans));
         }
     }
 }
@@ -0,0 +1,111 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::RealField;
+use nalgebra as na;
+use std::cell::{RefCell, RefMut};
+use std::fmt::Debug;
+use std::ops::DerefMut;
+use std::rc::{self, Rc};
+use std::sync::{self, Arc, Mutex, MutexGuard};
+
+use super::node::NodeImpl;
+
+/// Shared pointer and lock of `Node`
+///
+/// `Node`, `Chain` and the functions which use them are generic over the backend.
+/// `ArcMutexBackend` is the default, and `RcRefCellBackend` skips the locking cost
+/// in single-threaded programs.
+pub trait NodeBackend<T: RealField>: Debug + Sized + 'static {
+    /// Shared pointer to the node
+    type Shared: Clone + Debug;
+    /// Weak pointer to the node, for the parents
+    type Weak: Debug;
+    /// Guard of the locked node
+    type Guard<'a>: DerefMut<Target = NodeImpl<T, Self>>;
+
+    /// Share the node
+    fn new(node: NodeImpl<T, Self>) -> Self::Shared;
+    /// Lock the node, it panics if the node is already locked in the same thread
+    fn lock(node: &Self::Shared) -> Self::Guard<'_>;
+    /// Weak pointer to the node
+    fn downgrade(node: &Self::Shared) -> Self::Weak;
+    /// Shared pointer of the node if it is not dropped
+    fn upgrade(weak: &Self::Weak) -> Option<Self::Shared>;
+    /// The address of the node, which can be used as a key of maps
+    fn as_ptr(node: &Self::Shared) -> *const ();
+}
+
+/// `Arc<Mutex<NodeImpl>>`, the nodes are `Send` and `Sync`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArcMutexBackend;
+
+impl<T: RealField> NodeBackend<T> for ArcMutexBackend {
+    type Shared = Arc<Mutex<NodeImpl<T, Self>>>;
+    type Weak = sync::Weak<Mutex<NodeImpl<T, Self>>>;
+    type Guard<'a> = MutexGuard<'a, NodeImpl<T, Self>>;
+
+    #[inline]
+    fn new(node: NodeImpl<T, Self>) -> Self::Shared {
+        Arc::new(Mutex::new(node))
+    }
+    #[inline]
+    fn lock(node: &Self::Shared) -> Self::Guard<'_> {
+        node.lock().unwrap()
+    }
+    #[inline]
+    fn downgrade(node: &Self::Shared) -> Self::Weak {
+        Arc::downgrade(node)
+    }
+    #[inline]
+    fn upgrade(weak: &Self::Weak) -> Option<Self::Shared> {
+        weak.upgrade()
+    }
+    #[inline]
+    fn as_ptr(node: &Self::Shared) -> *const () {
+        Arc::as_ptr(node) as *const ()
+    }
+}
+
+/// `Rc<RefCell<NodeImpl>>`, faster than `ArcMutexBackend` but the nodes are not `Send`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RcRefCellBackend;
+
+impl<T: RealField> NodeBackend<T> for RcRefCellBackend {
+    type Shared = Rc<RefCell<NodeImpl<T, Self>>>;
+    type Weak = rc::Weak<RefCell<NodeImpl<T, Self>>>;
+    type Guard<'a> = RefMut<'a, NodeImpl<T, Self>>;
+
+    #[inline]
+    fn new(node: NodeImpl<T, Self>) -> Self::Shared {
+        Rc::new(RefCell::new(node))
+    }
+    #[inline]
+    fn lock(node: &Self::Shared) -> Self::Guard<'_> {
+        node.borrow_mut()
+    }
+    #[inline]
+    fn downgrade(node: &Self::Shared) -> Self::Weak {
+        Rc::downgrade(node)
+    }
+    #[inline]
+    fn upgrade(weak: &Self::Weak) -> Option<Self::Shared> {
+        weak.upgrade()
+    }
+    #[inline]
+    fn as_ptr(node: &Self::Shared) -> *const () {
+        Rc::as_ptr(node) as *const ()
+    }
+}
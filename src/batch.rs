@@ -0,0 +1,294 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use na::{Isometry3, Quaternion, RealField, Translation3, Unit, UnitQuaternion, Vector3};
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+
+use super::errors::*;
+use super::joint::*;
+use super::model::*;
+
+/// Poses of many samples in structure-of-arrays layout
+///
+/// `translation[axis][sample]` is the translation and `rotation[component][sample]` is
+/// the quaternion `[i, j, k, w]` of the sample.
+#[derive(Debug, Clone)]
+pub struct PoseBatch<T: RealField> {
+    /// x, y and z of the translations
+    pub translation: [Vec<T>; 3],
+    /// i, j, k and w of the rotations
+    pub rotation: [Vec<T>; 4],
+}
+
+impl<T: RealField> PoseBatch<T> {
+    /// Create `len` identity poses
+    pub fn identity(len: usize) -> Self {
+        let zeros = vec![T::zero(); len];
+        Self {
+            translation: [zeros.clone(), zeros.clone(), zeros.clone()],
+            rotation: [zeros.clone(), zeros.clone(), zeros, vec![T::one(); len]],
+        }
+    }
+    /// Number of the poses
+    pub fn len(&self) -> usize {
+        self.rotation[3].len()
+    }
+    /// `true` if there are no poses
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The pose of the `index`-th sample
+    pub fn pose(&self, index: usize) -> Isometry3<T> {
+        let [x, y, z] = &self.translation;
+        let [i, j, k, w] = &self.rotation;
+        Isometry3::from_parts(
+            Translation3::new(x[index], y[index], z[index]),
+            UnitQuaternion::new_unchecked(Quaternion::new(w[index], i[index], j[index], k[index])),
+        )
+    }
+
+    /// The translation and rotation lanes, all of them have `len()` elements
+    ///
+    /// The lengths are the same, so the loops over `0..len()` are not bounds checked.
+    #[inline]
+    fn lanes_mut(&mut self) -> ([&mut [T]; 3], [&mut [T]; 4]) {
+        let len = self.len();
+        let [x, y, z] = &mut self.translation;
+        let [i, j, k, w] = &mut self.rotation;
+        (
+            [&mut x[..len], &mut y[..len], &mut z[..len]],
+            [&mut i[..len], &mut j[..len], &mut k[..len], &mut w[..len]],
+        )
+    }
+
+    /// Multiply the same `(local_t, local_q)` from the right of all the poses
+    fn transform_by(&mut self, local_t: &[T; 3], local_q: &[T; 4]) {
+        let len = self.len();
+        let ([x, y, z], [i, j, k, w]) = self.lanes_mut();
+        for s in 0..len {
+            let q = [i[s], j[s], k[s], w[s]];
+            let moved = rotate(&q, local_t);
+            x[s] += moved[0];
+            y[s] += moved[1];
+            z[s] += moved[2];
+            let r = multiply(&q, local_q);
+            i[s] = r[0];
+            j[s] = r[1];
+            k[s] = r[2];
+            w[s] = r[3];
+        }
+    }
+
+    /// Rotate all the poses by `angles[sample]` about `axis`, the translations are not changed
+    fn rotate_about(&mut self, axis: &Unit<Vector3<T>>, angles: &[T]) {
+        let len = self.len();
+        let angles = &angles[..len];
+        let (_, [i, j, k, w]) = self.lanes_mut();
+        for s in 0..len {
+            let r = multiply(&[i[s], j[s], k[s], w[s]], &rotation_about(axis, angles[s]));
+            i[s] = r[0];
+            j[s] = r[1];
+            k[s] = r[2];
+            w[s] = r[3];
+        }
+    }
+
+    /// Move all the poses by `lengths[sample]` along `axis`, the rotations are not changed
+    fn translate_along(&mut self, axis: &Unit<Vector3<T>>, lengths: &[T]) {
+        let len = self.len();
+        let lengths = &lengths[..len];
+        let axis = [axis[0], axis[1], axis[2]];
+        let ([x, y, z], [i, j, k, w]) = self.lanes_mut();
+        for s in 0..len {
+            let moved = rotate(&[i[s], j[s], k[s], w[s]], &axis);
+            x[s] += moved[0] * lengths[s];
+            y[s] += moved[1] * lengths[s];
+            z[s] += moved[2] * lengths[s];
+        }
+    }
+
+    /// Multiply the transforms of `joint_type` from the right of all the poses
+    ///
+    /// `positions` is the positions of the joint in structure-of-arrays layout.
+    fn transform_by_joint(&mut self, joint_type: &JointType<T>, positions: &[T]) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let num_positions = joint_type.num_positions();
+        let positions = &positions[..num_positions * len];
+        let ([x, y, z], [i, j, k, w]) = self.lanes_mut();
+        let mut sample = [T::zero(); 7];
+        for s in 0..len {
+            for (value, lane) in sample.iter_mut().zip(positions.chunks_exact(len)) {
+                *value = lane[s];
+            }
+            let (local_t, local_q) =
+                to_arrays(&joint_transform_of(joint_type, &sample[..num_positions]));
+            let q = [i[s], j[s], k[s], w[s]];
+            let moved = rotate(&q, &local_t);
+            x[s] += moved[0];
+            y[s] += moved[1];
+            z[s] += moved[2];
+            let r = multiply(&q, &local_q);
+            i[s] = r[0];
+            j[s] = r[1];
+            k[s] = r[2];
+            w[s] = r[3];
+        }
+    }
+}
+
+/// Product of the quaternions `[i, j, k, w]`
+#[inline]
+fn multiply<T: RealField>(a: &[T; 4], b: &[T; 4]) -> [T; 4] {
+    [
+        a[3] * b[0] + a[0] * b[3] + a[1] * b[2] - a[2] * b[1],
+        a[3] * b[1] - a[0] * b[2] + a[1] * b[3] + a[2] * b[0],
+        a[3] * b[2] + a[0] * b[1] - a[1] * b[0] + a[2] * b[3],
+        a[3] * b[3] - a[0] * b[0] - a[1] * b[1] - a[2] * b[2],
+    ]
+}
+
+/// Rotate `v` by the unit quaternion `q`: `v + 2w(u x v) + 2u x (u x v)`
+#[inline]
+fn rotate<T: RealField>(q: &[T; 4], v: &[T; 3]) -> [T; 3] {
+    let two = T::one() + T::one();
+    let uv = [
+        q[1] * v[2] - q[2] * v[1],
+        q[2] * v[0] - q[0] * v[2],
+        q[0] * v[1] - q[1] * v[0],
+    ];
+    let uuv = [
+        q[1] * uv[2] - q[2] * uv[1],
+        q[2] * uv[0] - q[0] * uv[2],
+        q[0] * uv[1] - q[1] * uv[0],
+    ];
+    [
+        v[0] + two * (q[3] * uv[0] + uuv[0]),
+        v[1] + two * (q[3] * uv[1] + uuv[1]),
+        v[2] + two * (q[3] * uv[2] + uuv[2]),
+    ]
+}
+
+fn to_arrays<T: RealField>(trans: &Isometry3<T>) -> ([T; 3], [T; 4]) {
+    let t = &trans.translation.vector;
+    let q = trans.rotation.coords;
+    ([t[0], t[1], t[2]], [q[0], q[1], q[2], q[3]])
+}
+
+fn rotation_about<T: RealField>(axis: &Unit<Vector3<T>>, angle: T) -> [T; 4] {
+    let half: T = angle * na::convert(0.5);
+    let sin = half.sin();
+    [axis[0] * sin, axis[1] * sin, axis[2] * sin, half.cos()]
+}
+
+impl<T> Model<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    /// Calculate the world poses of the `end` node for many samples
+    ///
+    /// `positions` is in structure-of-arrays layout: the `i`-th position of the
+    /// `sample`-th sample is `positions[i * num_samples + sample]`, and `i` is the
+    /// same as `State::positions()`. The values of the mimic joints are ignored and
+    /// the limits are not checked.
+    ///
+    /// Each joint is applied to all the samples in one loop over the lanes of
+    /// `PoseBatch`, so the common joints (rotational and linear) are calculated
+    /// without any conversions. It returns `Err` if `end` is not in the model.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .name("end")
+    ///     .translation(Translation3::new(1.0, 0.0, 0.0))
+    ///     .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let model = Model::from_chain(&Chain::<f64>::from_root(l0)).unwrap();
+    /// // 3 samples, the angles of l0 and then the lengths of l1
+    /// let positions = [0.0, 1.0, -1.0, 0.5, 0.0, 1.0];
+    /// let poses = model.batch_end_poses(1, &positions, 3).unwrap();
+    /// assert_eq!(poses.len(), 3);
+    /// assert!((poses.translation[0][0] - 1.5).abs() < 1e-10);
+    /// assert!((poses.pose(2).translation.vector - Vector3::new(2.0 * 1f64.cos(), -2.0 * 1f64.sin(), 0.0)).norm() < 1e-10);
+    /// assert!(model.batch_end_poses(2, &positions, 3).is_err());
+    /// ```
+    pub fn batch_end_poses(
+        &self,
+        end: usize,
+        positions: &[T],
+        num_samples: usize,
+    ) -> Result<PoseBatch<T>, Error> {
+        if positions.len() != self.num_positions() * num_samples {
+            return Err(Error::SizeMismatchError {
+                input: positions.len(),
+                required: self.num_positions() * num_samples,
+            });
+        }
+        if end >= self.len() {
+            return Err(Error::NodeIndexOutOfRangeError {
+                index: end,
+                len: self.len(),
+            });
+        }
+        let positions = self.batch_coupled_positions(positions, num_samples);
+        let mut path = vec![end];
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        let mut poses = PoseBatch::identity(num_samples);
+        for index in path.into_iter().rev() {
+            let (origin_t, origin_q) = to_arrays(self.origin(index));
+            poses.transform_by(&origin_t, &origin_q);
+            let offset = self.position_offset(index) * num_samples;
+            let positions = &positions[offset..];
+            match *self.joint_type(index) {
+                JointType::Fixed => {}
+                JointType::Rotational { axis } | JointType::Continuous { axis } => {
+                    poses.rotate_about(&axis, positions);
+                }
+                JointType::Linear { axis } => {
+                    poses.translate_along(&axis, positions);
+                }
+                ref joint_type => poses.transform_by_joint(joint_type, positions),
+            }
+        }
+        Ok(poses)
+    }
+
+    /// The positions with the values of the mimic joints
+    fn batch_coupled_positions<'a>(&self, positions: &'a [T], num_samples: usize) -> Cow<'a, [T]> {
+        if !self.has_couplings() {
+            return Cow::Borrowed(positions);
+        }
+        let mut positions = positions.to_vec();
+        for sample in 0..num_samples {
+            self.apply_couplings(&mut positions, num_samples, sample);
+        }
+        Cow::Owned(positions)
+    }
+}
@@ -0,0 +1,358 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::{DMatrix, RealField, Vector3, Vector6};
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+
+use super::backend::*;
+use super::chain::*;
+use super::funcs::*;
+use super::node::*;
+use super::spatial::*;
+
+/// Inertia of the link and the payload of the node in the world frame
+///
+/// Call `Chain::update_transforms()` before using this.
+fn world_inertia<T, B>(node: &Node<T, B>) -> Option<SpatialInertia<T>>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    let trans = node.world_transform()?;
+    node.inertia().map(|inertia| inertia.transform(&trans))
+}
+
+/// Calculate the centroidal momentum of the chain
+///
+/// It uses the joint velocities of the chain (`Chain::set_joint_velocities`).
+/// `linear` of the output is the linear momentum and `angular` is the angular momentum
+/// around the center of mass, both in the world frame.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+/// use k::link::*;
+///
+/// let j0 = NodeBuilder::new()
+///     .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+///     .into_node();
+/// j0.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(2.0)).finalize()));
+/// let chain = Chain::<f64>::from_root(j0);
+/// chain.set_joint_velocities(&[0.5]).unwrap();
+/// let momentum = centroidal_momentum(&chain);
+/// assert!((momentum.linear - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-10);
+/// ```
+pub fn centroidal_momentum<T, B>(chain: &Chain<T, B>) -> Force<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    chain.update_velocities();
+    let com = center_of_mass(chain);
+    let mut momentum = Force::zero();
+    for node in chain.iter() {
+        let velocity = match node.world_velocity() {
+            Some(velocity) => velocity,
+            None => continue,
+        };
+        if let Some(inertia) = world_inertia(node) {
+            let p = node
+                .world_transform()
+                .expect("cache must exist")
+                .translation
+                .vector;
+            let com_velocity = velocity.translation + velocity.rotation.cross(&(inertia.com - p));
+            let linear = com_velocity * inertia.mass;
+            momentum.linear += linear;
+            momentum.angular +=
+                (inertia.com - com).cross(&linear) + inertia.inertia * velocity.rotation;
+        }
+    }
+    momentum
+}
+
+/// Calculate the centroidal momentum matrix of the chain
+///
+/// It is a `6 x active_dof` matrix which maps the velocities of the active joints to the
+/// centroidal momentum. The rows are `[linear, angular]` and the columns are the same as
+/// `jacobian`, the mimic joints are added to the columns of the joints which drive them.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+/// use k::link::*;
+///
+/// let j0 = NodeBuilder::new()
+///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+///     .into_node();
+/// let j1 = NodeBuilder::new()
+///     .translation(Translation3::new(1.0, 0.0, 0.0))
+///     .into_node();
+/// j1.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(2.0)).finalize()));
+/// j1.set_parent(&j0);
+/// let chain = Chain::<f64>::from_root(j0);
+/// let cmm = centroidal_momentum_matrix(&chain);
+/// assert_eq!(cmm.shape(), (6, 1));
+/// // linear momentum for unit velocity is m * v
+/// assert!((cmm[(1, 0)] - 2.0).abs() < 1e-10);
+/// ```
+pub fn centroidal_momentum_matrix<T, B>(chain: &Chain<T, B>) -> DMatrix<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    let com = center_of_mass(chain);
+    let (columns, dof) = jacobian_columns(chain.active_nodes());
+    let column_of =
+        |node: &Node<T, B>| columns.iter().find(|(n, _)| n == node).map(|(_, col)| *col);
+    let mut cmm = DMatrix::zeros(6, dof);
+    for node in chain.iter() {
+        let inertia = match world_inertia(node) {
+            Some(inertia) => inertia,
+            None => continue,
+        };
+        for ancestor in node.iter_ancestors() {
+            let momentums = {
+                let joint = ancestor.joint();
+                if !joint.is_movable() {
+                    continue;
+                }
+                let trans = joint.world_transform().expect("cache must exist");
+                // rotation of the origin frame of the joint
+                let rotation = trans.rotation * joint.joint_transform().rotation.inverse();
+                (0..joint.joint_type.dof())
+                    .map(|index| {
+                        let motion = joint.motion_subspace(index);
+                        let linear_motion = rotation * motion.linear;
+                        let angular_motion = rotation * motion.angular;
+                        let com_velocity = linear_motion
+                            + angular_motion.cross(&(inertia.com - trans.translation.vector));
+                        let linear = com_velocity * inertia.mass;
+                        let angular =
+                            inertia.inertia * angular_motion + (inertia.com - com).cross(&linear);
+                        Vector6::new(
+                            linear[0], linear[1], linear[2], angular[0], angular[1], angular[2],
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            match column_of(&ancestor) {
+                Some(col) => {
+                    for (i, momentum) in momentums.iter().enumerate() {
+                        let mut column = cmm.column_mut(col + i);
+                        column += momentum;
+                    }
+                }
+                // mimic joint: the velocity is folded into the joints which drive it
+                None => {
+                    add_to_driving_columns(&ancestor, &momentums[0], T::one(), &column_of, &mut cmm)
+                }
+            }
+        }
+    }
+    cmm
+}
+
+/// Calculate the Zero Moment Point on the floor
+///
+/// It uses the joint velocities and accelerations of the chain
+/// (`Chain::set_joint_velocities`, `Chain::set_joint_accelerations`).
+/// The root of the chain is assumed to be fixed on the world and the gravity
+/// is `-gravity` along the z axis. It returns `None` if the vertical force is not
+/// positive (the robot is not on the floor).
+///
+/// From 'Humanoid Robot (Kajita)' P.79
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+/// use k::link::*;
+///
+/// let j0 = NodeBuilder::new()
+///     .translation(Translation3::new(0.1, 0.2, 1.0))
+///     .into_node();
+/// j0.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(2.0)).finalize()));
+/// let chain = Chain::<f64>::from_root(j0);
+/// // static: ZMP is the projection of the center of mass
+/// let zmp = zero_moment_point(&chain, 9.8, 0.0).unwrap();
+/// assert!((zmp - Vector3::new(0.1, 0.2, 0.0)).norm() < 1e-10);
+/// ```
+pub fn zero_moment_point<T, B>(
+    chain: &Chain<T, B>,
+    gravity: T,
+    floor_height: T,
+) -> Option<Vector3<T>>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    chain.update_accelerations();
+    let mut total_mass = T::zero();
+    let mut mass_position = Vector3::zeros();
+    // derivatives of linear momentum and angular momentum around the world origin
+    let mut d_linear = Vector3::zeros();
+    let mut d_angular = Vector3::zeros();
+    for node in chain.iter() {
+        let inertia = match world_inertia(node) {
+            Some(inertia) => inertia,
+            None => continue,
+        };
+        let p = node
+            .world_transform()
+            .expect("cache must exist")
+            .translation
+            .vector;
+        let w = node.world_velocity().expect("cache must exist").rotation;
+        let acceleration = node.world_acceleration().expect("cache must exist");
+        let r = inertia.com - p;
+        let com_acceleration =
+            acceleration.linear + acceleration.angular.cross(&r) + w.cross(&w.cross(&r));
+        let force = com_acceleration * inertia.mass;
+        total_mass += inertia.mass;
+        mass_position += inertia.com * inertia.mass;
+        d_linear += force;
+        d_angular += inertia.com.cross(&force)
+            + inertia.inertia * acceleration.angular
+            + w.cross(&(inertia.inertia * w));
+    }
+    let vertical_force = total_mass * gravity + d_linear.z;
+    if vertical_force <= T::zero() {
+        return None;
+    }
+    let x = (mass_position.x * gravity + floor_height * d_linear.x - d_angular.y) / vertical_force;
+    let y = (mass_position.y * gravity + floor_height * d_linear.y + d_angular.x) / vertical_force;
+    Some(Vector3::new(x, y, floor_height))
+}
+
+#[cfg(test)]
+fn create_chain_with_links() -> Chain<f64> {
+    use super::joint::*;
+    use super::link::*;
+    use na::{Isometry3, Matrix3, Translation3, UnitQuaternion};
+    let link = |mass: f64, com: Translation3<f64>| {
+        Some(
+            LinkBuilder::new()
+                .inertial(Inertial::new(
+                    Isometry3::from_parts(com, UnitQuaternion::from_euler_angles(0.1, 0.2, 0.3)),
+                    mass,
+                    Matrix3::new(0.1, 0.01, 0.0, 0.01, 0.2, 0.0, 0.0, 0.0, 0.3),
+                ))
+                .finalize(),
+        )
+    };
+    let j0 = NodeBuilder::new()
+        .translation(Translation3::new(0.0, 0.0, 0.5))
+        .joint_type(JointType::Rotational {
+            axis: Vector3::z_axis(),
+        })
+        .into_node();
+    let j1 = NodeBuilder::new()
+        .translation(Translation3::new(0.1, 0.0, 0.2))
+        .joint_type(JointType::Linear {
+            axis: Vector3::x_axis(),
+        })
+        .into_node();
+    let j2 = NodeBuilder::new()
+        .translation(Translation3::new(0.0, 0.2, 0.1))
+        .joint_type(JointType::Rotational {
+            axis: Vector3::y_axis(),
+        })
+        .into_node();
+    j0.set_link(link(3.0, Translation3::new(0.0, 0.0, 0.1)));
+    j1.set_link(link(1.0, Translation3::new(0.1, 0.0, 0.0)));
+    j2.set_link(link(0.5, Translation3::new(0.0, 0.1, -0.1)));
+    j1.set_parent(&j0);
+    j2.set_parent(&j0);
+    Chain::from_root(j0)
+}
+
+#[test]
+fn test_centroidal_momentum_matrix() {
+    let chain = create_chain_with_links();
+    let velocities = vec![0.3, -0.5, 1.2];
+    chain.set_joint_positions(&[0.2, 0.1, -0.4]).unwrap();
+    chain.set_joint_velocities(&velocities).unwrap();
+    let momentum = centroidal_momentum(&chain);
+    let by_matrix = centroidal_momentum_matrix(&chain) * na::DVector::from_vec(velocities);
+    assert!(
+        (by_matrix - na::DVector::from_row_slice(momentum.to_vector().as_slice())).norm() < 1e-10
+    );
+}
+
+#[test]
+fn test_centroidal_momentum_matrix_mimic() {
+    use super::joint::Mimic;
+    let chain = create_chain_with_links();
+    let j0 = chain.iter().next().unwrap();
+    chain
+        .iter()
+        .last()
+        .unwrap()
+        .set_mimic_parent(j0, Mimic::new(2.0, 0.1));
+    chain.set_joint_positions(&[0.2, 0.1, 0.0]).unwrap();
+    chain.set_joint_velocities(&[0.3, -0.5, 0.0]).unwrap();
+    let momentum = centroidal_momentum(&chain);
+    let cmm = centroidal_momentum_matrix(&chain);
+    // the mimic joint has no column
+    assert_eq!(cmm.shape(), (6, 2));
+    let by_matrix = cmm * na::DVector::from_vec(vec![0.3, -0.5]);
+    assert!(
+        (by_matrix - na::DVector::from_row_slice(momentum.to_vector().as_slice())).norm() < 1e-10
+    );
+}
+
+#[test]
+fn test_zero_moment_point() {
+    let chain = create_chain_with_links();
+    chain.set_joint_positions(&[0.2, 0.1, -0.4]).unwrap();
+    chain.set_joint_velocities(&[0.3, -0.5, 1.2]).unwrap();
+    chain.set_joint_accelerations(&[0.0, 0.0, 0.0]).unwrap();
+    // The moment around ZMP must not have horizontal components.
+    // It is calculated by the time derivative of the centroidal momentum.
+    let gravity = 9.8;
+    let zmp = zero_moment_point(&chain, gravity, 0.0).unwrap();
+    let dt = 1e-6;
+    let momentum_at = |sign: f64| {
+        let q = chain.joint_positions();
+        let dq = chain.joint_velocities();
+        let next = q
+            .iter()
+            .zip(dq.iter())
+            .map(|(q, dq)| q + dq * dt * sign)
+            .collect::<Vec<_>>();
+        chain.set_joint_positions(&next).unwrap();
+        let com = center_of_mass(&chain);
+        let h = centroidal_momentum(&chain);
+        chain.set_joint_positions(&q).unwrap();
+        (com, h)
+    };
+    let (com_p, h_p) = momentum_at(1.0);
+    let (com_m, h_m) = momentum_at(-1.0);
+    let d_linear = (h_p.linear - h_m.linear) / (2.0 * dt);
+    // angular momentum around the world origin
+    let l_p = h_p.angular + com_p.cross(&h_p.linear);
+    let l_m = h_m.angular + com_m.cross(&h_m.linear);
+    let d_angular = (l_p - l_m) / (2.0 * dt);
+    let com = center_of_mass(&chain);
+    let total_mass = 4.5;
+    let gravity_force = Vector3::new(0.0, 0.0, -total_mass * gravity);
+    let moment = d_angular - com.cross(&gravity_force) - zmp.cross(&(d_linear - gravity_force));
+    assert!(moment.x.abs() < 1e-5);
+    assert!(moment.y.abs() < 1e-5);
+}
@@ -13,14 +13,20 @@
   See the License for the specific language governing permissions and
   limitations under the License.
 */
+use super::backend::*;
+use super::closure::*;
 use super::errors::*;
 use super::joint::*;
 use super::node::*;
+use super::spatial::Motion;
+use super::transmission::*;
 use na::{Isometry3, RealField};
 use nalgebra as na;
 use simba::scalar::SubsetOf;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display};
 use std::ops::Deref;
+use std::sync::Arc;
 
 /// Kinematic Chain using `Node`
 ///
@@ -83,16 +89,120 @@ use std::ops::Deref;
 /// }
 /// ```
 #[derive(Debug)]
-pub struct Chain<T: RealField> {
-    nodes: Vec<Node<T>>,
-    movable_nodes: Vec<Node<T>>,
+pub struct Chain<T: RealField, B: NodeBackend<T> = ArcMutexBackend> {
+    nodes: Vec<Node<T, B>>,
+    movable_nodes: Vec<Node<T, B>>,
     dof: usize,
+    num_positions: usize,
+    passive_nodes: Vec<Node<T, B>>,
+    loop_closures: Vec<LoopClosure<T, B>>,
+    transmissions: Vec<Transmission<T>>,
+    /// joint name to the index of `nodes`
+    joint_indices: HashMap<String, usize>,
+    /// link name to the index of `nodes`
+    link_indices: HashMap<String, usize>,
+    /// index of the first position of each node in `joint_positions()`
+    position_offsets: Vec<usize>,
+    /// shared with the `JointHandle`s, replaced when `position_offsets` are shifted
+    positions_layout: Arc<()>,
 }
 
-impl<T: RealField + SubsetOf<f64>> Chain<T> {
+fn check_size<T>(values: &[T], required: usize) -> Result<(), Error> {
+    if values.len() != required {
+        return Err(Error::SizeMismatchError {
+            input: values.len(),
+            required,
+        });
+    }
+    Ok(())
+}
+
+/// Split `values` into the slices for `nodes`
+///
+/// `size` returns the number of the values for each joint.
+fn split_values<'a, T, B, F>(
+    nodes: &'a [Node<T, B>],
+    values: &'a [T],
+    size: F,
+) -> Vec<(&'a Node<T, B>, &'a [T])>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+    F: Fn(&JointType<T>) -> usize,
+{
+    let mut offset = 0;
+    nodes
+        .iter()
+        .map(|node| {
+            let len = size(&node.joint().joint_type);
+            let split = (node, &values[offset..offset + len]);
+            offset += len;
+            split
+        })
+        .collect()
+}
+
+/// The position of `node` after setting `new_positions`, following the mimic couplings
+fn coupled_position<T, B>(node: &Node<T, B>, new_positions: &HashMap<*const (), T>) -> Option<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    if let Some(position) = new_positions.get(&node.as_ptr()) {
+        return Some(*position);
+    }
+    match node.coupling() {
+        Some(coupling) => {
+            let parent_positions = node
+                .mimic_parents()
+                .iter()
+                .map(|parent| coupled_position(parent, new_positions).unwrap_or_else(T::zero))
+                .collect::<Vec<_>>();
+            Some(coupling.position(&parent_positions))
+        }
+        None => node.joint_position(),
+    }
+}
+
+/// Update the cache of the world transform of the node from its parent
+///
+/// The valid cache is used as is, it is cleared when the joint or its ancestors move.
+fn update_world_transform<T, B>(node: &Node<T, B>) -> Isometry3<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    if let Some(trans) = node.world_transform() {
+        return trans;
+    }
+    let parent_transform = node.parent_world_transform().expect("cache must exist");
+    let trans = parent_transform * node.joint().local_transform();
+    node.joint().set_world_transform(trans);
+    trans
+}
+
+/// Sum of the degrees of freedom and the number of positions of the joints
+fn count_dimensions<'a, T, B, I>(movable_nodes: I) -> (usize, usize)
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+    I: IntoIterator<Item = &'a Node<T, B>>,
+{
+    movable_nodes
+        .into_iter()
+        .fold((0, 0), |(dof, num_positions), node| {
+            let joint_type = node.joint().joint_type;
+            (
+                dof + joint_type.dof(),
+                num_positions + joint_type.num_positions(),
+            )
+        })
+}
+
+impl<T: RealField + SubsetOf<f64>, B: NodeBackend<T>> Chain<T, B> {
     fn fmt_with_indent_level(
         &self,
-        node: &Node<T>,
+        node: &Node<T, B>,
         level: usize,
         f: &mut fmt::Formatter,
     ) -> fmt::Result {
@@ -106,13 +216,13 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     }
 }
 
-impl<T: RealField + SubsetOf<f64>> Display for Chain<T> {
+impl<T: RealField + SubsetOf<f64>, B: NodeBackend<T>> Display for Chain<T, B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.fmt_with_indent_level(&self.iter().next().unwrap(), 0, f)
     }
 }
 
-impl<T: RealField + SubsetOf<f64>> Chain<T> {
+impl<T: RealField + SubsetOf<f64>, B: NodeBackend<T>> Chain<T, B> {
     /// Create Chain from root joint
     ///
     /// # Examples
@@ -126,18 +236,57 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     /// let tree = k::Chain::<f32>::from_root(l0);
     /// ```
     #[allow(clippy::needless_pass_by_value)]
-    pub fn from_root(root_joint: Node<T>) -> Self {
-        let nodes = root_joint.iter_descendants().collect::<Vec<_>>();
-        let movable_nodes = nodes
+    pub fn from_root(root_joint: Node<T, B>) -> Self {
+        Self::from_nodes(root_joint.iter_descendants().collect())
+    }
+    /// Chain without any nodes
+    pub(crate) fn empty() -> Self {
+        Self::from_nodes(Vec::new())
+    }
+    /// Create `Chain` from the nodes sorted from parent to children
+    pub(crate) fn from_nodes(nodes: Vec<Node<T, B>>) -> Self {
+        let mut chain = Chain {
+            dof: 0,
+            num_positions: 0,
+            nodes: Vec::new(),
+            movable_nodes: Vec::new(),
+            passive_nodes: Vec::new(),
+            loop_closures: Vec::new(),
+            transmissions: Vec::new(),
+            joint_indices: HashMap::new(),
+            link_indices: HashMap::new(),
+            position_offsets: Vec::new(),
+            positions_layout: Arc::new(()),
+        };
+        chain.set_nodes(nodes);
+        chain
+    }
+    /// Replace the nodes, and update the movable nodes, the dimensions and the indices
+    fn set_nodes(&mut self, nodes: Vec<Node<T, B>>) {
+        self.movable_nodes = nodes
             .iter()
             .filter(|joint| joint.joint().is_movable())
             .cloned()
             .collect::<Vec<_>>();
-        Chain {
-            dof: movable_nodes.len(),
-            nodes,
-            movable_nodes,
+        let (dof, num_positions) = count_dimensions(&self.movable_nodes);
+        self.dof = dof;
+        self.num_positions = num_positions;
+        self.joint_indices.clear();
+        self.link_indices.clear();
+        self.position_offsets.clear();
+        let mut offset = 0;
+        for (index, node) in nodes.iter().enumerate() {
+            self.position_offsets.push(offset);
+            offset += node.joint().joint_type.num_positions();
+            // the first one is used for the duplicated names, same as the linear search
+            self.joint_indices
+                .entry(node.joint().name.clone())
+                .or_insert(index);
+            if let Some(link) = node.link().as_ref() {
+                self.link_indices.entry(link.name.clone()).or_insert(index);
+            }
         }
+        self.nodes = nodes;
     }
     /// Create `Chain` from end joint. It has any branches.
     ///
@@ -159,19 +308,10 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     ///
     /// let tree = create_tree_from_end(); // no problem
     /// ```
-    pub fn from_end(end_joint: &Node<T>) -> Chain<T> {
+    pub fn from_end(end_joint: &Node<T, B>) -> Chain<T, B> {
         let mut nodes = end_joint.iter_ancestors().collect::<Vec<_>>();
         nodes.reverse();
-        let movable_nodes = nodes
-            .iter()
-            .filter(|joint| joint.joint().is_movable())
-            .cloned()
-            .collect::<Vec<_>>();
-        Chain {
-            dof: movable_nodes.len(),
-            movable_nodes,
-            nodes,
-        }
+        Self::from_nodes(nodes)
     }
     /// Set the `Chain`'s origin
     ///
@@ -218,7 +358,7 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     /// assert_eq!(names[0], "fixed0");
     /// assert_eq!(names[1], "fixed1");
     /// ```
-    pub fn iter(&self) -> impl Iterator<Item = &Node<T>> {
+    pub fn iter(&self) -> impl Iterator<Item = &Node<T, B>> {
         self.nodes.iter()
     }
 
@@ -226,12 +366,12 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     ///
     /// Fixed joints are ignored. If you want to manipulate on Fixed,
     /// use `iter()` instead of `iter_joints()`
-    pub fn iter_joints(&self) -> impl Iterator<Item = JointRefGuard<T>> {
+    pub fn iter_joints(&self) -> impl Iterator<Item = JointRefGuard<T, B>> {
         self.movable_nodes.iter().map(|node| node.joint())
     }
 
     /// Iterate for links
-    pub fn iter_links(&self) -> impl Iterator<Item = LinkRefGuard<T>> {
+    pub fn iter_links(&self) -> impl Iterator<Item = LinkRefGuard<T, B>> {
         self.nodes.iter().filter_map(|node| {
             if node.link().is_some() {
                 Some(LinkRefGuard { guard: node.lock() })
@@ -242,6 +382,12 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     }
     /// Calculate the degree of freedom
     ///
+    /// It is the dimension of the velocities (tangent space). The dimension of
+    /// the positions is `num_positions()`, which is larger if the chain has
+    /// `Floating` joints.
+    ///
+    /// The mimic joints are also counted, use `active_dof()` to ignore them.
+    ///
     /// # Examples
     ///
     /// ```
@@ -261,6 +407,346 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     pub fn dof(&self) -> usize {
         self.dof
     }
+    /// Number of the positions of the joints (dimension of the configuration space)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Floating)
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let tree = Chain::<f64>::from_root(l0);
+    /// assert_eq!(tree.dof(), 7);
+    /// assert_eq!(tree.num_positions(), 8);
+    /// assert_eq!(tree.joint_positions().len(), 8);
+    /// ```
+    pub fn num_positions(&self) -> usize {
+        self.num_positions
+    }
+
+    /// Movable nodes which are not driven by other joints
+    ///
+    /// The mimic joints and the passive joints are ignored.
+    /// It is calculated every time because the mimic relations can be changed
+    /// after the chain is created.
+    pub fn active_nodes(&self) -> Vec<Node<T, B>> {
+        self.iter_active_nodes().cloned().collect()
+    }
+
+    /// Iterate the active nodes without allocation
+    pub(crate) fn iter_active_nodes(&self) -> impl Iterator<Item = &Node<T, B>> {
+        self.movable_nodes
+            .iter()
+            .filter(move |node| self.is_active(node))
+    }
+
+    /// `true` if the movable node is not driven by other joints
+    pub(crate) fn is_active(&self, node: &Node<T, B>) -> bool {
+        !node.is_mimic() && !self.passive_nodes.contains(node)
+    }
+
+    /// Set the passive joints, which are moved to close the loops
+    ///
+    /// They are not active joints and `LoopClosureSolver` calculates their positions.
+    pub fn set_passive_nodes(&mut self, nodes: &[&Node<T, B>]) {
+        self.passive_nodes = nodes.iter().map(|node| (*node).clone()).collect();
+    }
+
+    /// The passive joints of the closed loops
+    pub fn passive_nodes(&self) -> &[Node<T, B>] {
+        &self.passive_nodes
+    }
+
+    /// Add a constraint to close a kinematic loop
+    pub fn add_loop_closure(&mut self, closure: LoopClosure<T, B>) {
+        self.loop_closures.push(closure);
+    }
+
+    /// The constraints of the closed loops
+    pub fn loop_closures(&self) -> &[LoopClosure<T, B>] {
+        &self.loop_closures
+    }
+
+    /// Set the mappings between the actuators and the joints
+    ///
+    /// They are loaded from `<transmission>` of URDF by `Chain::from_urdf_file()` and
+    /// `Chain::from_urdf_str()`.
+    pub fn set_transmissions(&mut self, transmissions: Vec<Transmission<T>>) {
+        self.transmissions = transmissions;
+    }
+
+    /// The mappings between the actuators and the joints
+    pub fn transmissions(&self) -> &[Transmission<T>] {
+        &self.transmissions
+    }
+
+    /// Attach `other` under `parent` with the mounting pose `origin`
+    ///
+    /// The origin of the root of `other` is replaced by `origin`. The nodes of `other`
+    /// are added after the current nodes, so the order of the current positions is kept.
+    /// The passive nodes, the loop closures and the transmissions of `other` are also
+    /// moved to this chain.
+    ///
+    /// It returns `SharedNodeError` if `other` has a node of this chain,
+    /// `DuplicateJointNameError` if a name of the joints of `other` is already used, and
+    /// `DuplicateLinkNameError` if a name of the links of `other` is already used.
+    /// Nothing is changed in those cases.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let mut arm = Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    /// let finger = NodeBuilder::new()
+    ///     .name("finger")
+    ///     .joint_type(JointType::Linear{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// let gripper = Chain::from_root(finger);
+    /// let wrist = arm.find("l_wrist_pitch").unwrap().clone();
+    /// arm.attach(gripper, &wrist, Isometry3::translation(0.0, 0.0, -0.1)).unwrap();
+    /// assert_eq!(arm.dof(), 13);
+    /// assert_eq!(arm.find("finger").unwrap().parent().unwrap(), wrist);
+    ///
+    /// let finger = arm.find("finger").unwrap().clone();
+    /// let gripper = arm.detach(&finger).unwrap();
+    /// assert_eq!(arm.dof(), 12);
+    /// assert!(arm.find("finger").is_none());
+    /// assert_eq!(gripper.dof(), 1);
+    /// ```
+    pub fn attach(
+        &mut self,
+        other: Chain<T, B>,
+        parent: &Node<T, B>,
+        origin: Isometry3<T>,
+    ) -> Result<(), Error> {
+        if !self.nodes.contains(parent) {
+            return Err(Error::JointNotFoundError {
+                joint_name: parent.joint().name.clone(),
+            });
+        }
+        let own_nodes = self.nodes.iter().map(Node::as_ptr).collect::<HashSet<_>>();
+        let own_links = self
+            .nodes
+            .iter()
+            .filter_map(|node| node.link().as_ref().map(|link| link.name.clone()))
+            .collect::<HashSet<_>>();
+        for node in &other.nodes {
+            if own_nodes.contains(&node.as_ptr()) {
+                return Err(Error::SharedNodeError {
+                    joint_name: node.joint().name.clone(),
+                });
+            }
+            let joint_name = node.joint().name.clone();
+            if self.joint_indices.contains_key(&joint_name) {
+                return Err(Error::DuplicateJointNameError { joint_name });
+            }
+            if let Some(link) = node.link().as_ref() {
+                if own_links.contains(&link.name) {
+                    return Err(Error::DuplicateLinkNameError {
+                        link_name: link.name.clone(),
+                    });
+                }
+            }
+        }
+        let root = match other.nodes.first() {
+            Some(root) => root.clone(),
+            None => return Ok(()),
+        };
+        if let Some(old_parent) = root.parent() {
+            root.remove_parent(&old_parent);
+        }
+        root.set_origin(origin);
+        root.set_parent(parent);
+        let mut nodes = std::mem::take(&mut self.nodes);
+        nodes.extend(other.nodes);
+        self.set_nodes(nodes);
+        self.passive_nodes.extend(other.passive_nodes);
+        self.loop_closures.extend(other.loop_closures);
+        self.transmissions.extend(other.transmissions);
+        Ok(())
+    }
+
+    /// Detach `node` and its descendants from this chain
+    ///
+    /// It returns them as a new `Chain` whose root is `node`. The passive nodes and the
+    /// transmissions of the detached joints are moved to it, and the loop closures are
+    /// moved if both of the nodes are detached, or removed if one of them is detached.
+    /// The mimic couplings between the detached joints and the kept joints are removed,
+    /// so those joints keep their current positions and become independent.
+    ///
+    /// It returns `DetachRootError` for the root of this chain, because the chain
+    /// can not be empty.
+    ///
+    /// The indices of the positions after the detached joints are shifted, so the
+    /// `JointHandle`s of this chain are invalidated and must be created again.
+    pub fn detach(&mut self, node: &Node<T, B>) -> Result<Chain<T, B>, Error> {
+        if !self.nodes.contains(node) {
+            return Err(Error::JointNotFoundError {
+                joint_name: node.joint().name.clone(),
+            });
+        }
+        if *node == self.nodes[0] {
+            return Err(Error::DetachRootError {
+                joint_name: node.joint().name.clone(),
+            });
+        }
+        if let Some(parent) = node.parent() {
+            node.remove_parent(&parent);
+        }
+        let mut detached = Chain::from_root(node.clone());
+        let detached_nodes = detached.iter().map(Node::as_ptr).collect::<HashSet<_>>();
+        let is_detached = |node: &Node<T, B>| detached_nodes.contains(&node.as_ptr());
+
+        for node in self.nodes.iter() {
+            if node
+                .mimic_parents()
+                .iter()
+                .any(|parent| is_detached(parent) != is_detached(node))
+            {
+                node.clear_coupling();
+            }
+        }
+        let (_, nodes): (Vec<_>, Vec<_>) = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .partition(|node| is_detached(node));
+        self.set_nodes(nodes);
+        self.positions_layout = Arc::new(());
+        let (moved, kept) = std::mem::take(&mut self.passive_nodes)
+            .into_iter()
+            .partition(|node| is_detached(node));
+        detached.passive_nodes = moved;
+        self.passive_nodes = kept;
+        let (moved, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.loop_closures)
+            .into_iter()
+            .partition(|closure| is_detached(&closure.node1) || is_detached(&closure.node2));
+        detached.loop_closures = moved
+            .into_iter()
+            .filter(|closure| is_detached(&closure.node1) && is_detached(&closure.node2))
+            .collect();
+        self.loop_closures = kept;
+        let (moved, kept) = std::mem::take(&mut self.transmissions)
+            .into_iter()
+            .partition(|transmission| detached.find(&transmission.joint_name).is_some());
+        detached.transmissions = moved;
+        self.transmissions = kept;
+        Ok(detached)
+    }
+
+    /// Calculate the degree of freedom without the mimic joints
+    ///
+    /// It is the number of the columns of `jacobian` and the dimension of the
+    /// variables of the inverse kinematics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// l1.set_mimic_parent(&l0, k::joint::Mimic::new(-1.0, 0.0));
+    /// let tree = Chain::<f64>::from_root(l0);
+    /// assert_eq!(tree.dof(), 2);
+    /// assert_eq!(tree.active_dof(), 1);
+    /// ```
+    pub fn active_dof(&self) -> usize {
+        count_dimensions(self.iter_active_nodes()).0
+    }
+
+    /// Number of the positions of the joints without the mimic joints
+    pub fn num_active_positions(&self) -> usize {
+        count_dimensions(self.iter_active_nodes()).1
+    }
+
+    /// Get the positions of the joints without the mimic joints
+    ///
+    /// The length is the same with `num_active_positions()`
+    pub fn active_joint_positions(&self) -> Vec<T> {
+        let mut positions = Vec::new();
+        for node in self.active_nodes() {
+            positions.extend_from_slice(node.joint().joint_positions());
+        }
+        positions
+    }
+
+    /// Set the positions of the joints without the mimic joints
+    ///
+    /// The mimic joints follow the new positions.
+    /// The input number must be equal with `num_active_positions()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// l1.set_mimic_parent(&l0, k::joint::Mimic::new(-1.0, 0.0));
+    /// let tree = Chain::<f64>::from_root(l0);
+    /// tree.set_active_joint_positions(&[0.5]).unwrap();
+    /// assert_eq!(tree.joint_positions(), vec![0.5, -0.5]);
+    /// ```
+    pub fn set_active_joint_positions(&self, positions_vec: &[T]) -> Result<(), Error> {
+        let active_nodes = self.active_nodes();
+        check_size(positions_vec, count_dimensions(&active_nodes).1)?;
+        for (node, positions) in
+            split_values(&active_nodes, positions_vec, JointType::num_positions)
+        {
+            node.set_joint_positions(positions)?;
+        }
+        Ok(())
+    }
+
+    /// Set the clamped positions of the joints without the mimic joints
+    pub fn set_active_joint_positions_clamped(&self, positions_vec: &[T]) {
+        let active_nodes = self.active_nodes();
+        if positions_vec.len() != count_dimensions(&active_nodes).1 {
+            return;
+        }
+        for (node, positions) in
+            split_values(&active_nodes, positions_vec, JointType::num_positions)
+        {
+            node.set_joint_positions_clamped(positions);
+        }
+    }
+
+    /// `integrate_joint_positions` for the joints without the mimic joints
+    ///
+    /// The length of `positions` is `num_active_positions()` and the length of
+    /// `velocities` is `active_dof()`.
+    pub fn integrate_active_joint_positions(
+        &self,
+        positions_vec: &[T],
+        velocities_vec: &[T],
+    ) -> Result<Vec<T>, Error> {
+        let active_nodes = self.active_nodes();
+        let (dof, num_positions) = count_dimensions(&active_nodes);
+        check_size(positions_vec, num_positions)?;
+        check_size(velocities_vec, dof)?;
+        let mut output = Vec::with_capacity(num_positions);
+        for ((node, positions), (_, velocities)) in
+            split_values(&active_nodes, positions_vec, JointType::num_positions)
+                .into_iter()
+                .zip(split_values(&active_nodes, velocities_vec, JointType::dof))
+        {
+            output.extend(node.joint().integrate_positions(positions, velocities));
+        }
+        Ok(output)
+    }
     /// Find the joint by name
     ///
     /// # Examples
@@ -282,34 +768,74 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     /// j.set_joint_position(0.5).unwrap();
     /// assert_eq!(j.joint_position().unwrap(), 0.5);
     /// ```
-    pub fn find(&self, joint_name: &str) -> Option<&Node<T>> {
-        self.iter().find(|joint| joint.joint().name == joint_name)
+    pub fn find(&self, joint_name: &str) -> Option<&Node<T, B>> {
+        self.joint_indices
+            .get(joint_name)
+            .map(|index| &self.nodes[*index])
+    }
+    /// Index of the first position of the joint in `joint_positions()`
+    pub(crate) fn position_offset(&self, joint_name: &str) -> Option<usize> {
+        self.joint_indices
+            .get(joint_name)
+            .map(|index| self.position_offsets[*index])
+    }
+    /// It is replaced when the indices of the positions are shifted
+    pub(crate) fn positions_layout(&self) -> &Arc<()> {
+        &self.positions_layout
+    }
+    /// Find the node by the name of its link
+    ///
+    /// The links are indexed when the `Chain` is created. The links which are set after
+    /// that are found by the linear search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let chain = k::Chain::<f32>::from_urdf_file("urdf/sample.urdf").unwrap();
+    /// let node = chain.find_link("l_wrist2").unwrap();
+    /// assert_eq!(node.joint().name, "l_wrist_pitch");
+    /// assert!(chain.find_link("no_such_link").is_none());
+    /// ```
+    pub fn find_link(&self, link_name: &str) -> Option<&Node<T, B>> {
+        let has_link = |node: &&Node<T, B>| {
+            node.link()
+                .as_ref()
+                .map_or(false, |link| link.name == link_name)
+        };
+        self.link_indices
+            .get(link_name)
+            .map(|index| &self.nodes[*index])
+            .filter(has_link)
+            .or_else(|| self.iter().find(has_link))
     }
     /// Get the positions of the joints
     ///
-    /// `FixedJoint` is ignored. the length is the same with `dof()`
+    /// `FixedJoint` is ignored. the length is the same with `num_positions()`
     pub fn joint_positions(&self) -> Vec<T> {
-        self.iter_joints()
-            .map(|joint| {
-                joint
-                    .joint_position()
-                    .expect("Must be a bug: movable joint must have position")
-            })
-            .collect()
+        let mut positions = Vec::with_capacity(self.num_positions);
+        for joint in self.iter_joints() {
+            positions.extend_from_slice(joint.joint_positions());
+        }
+        positions
     }
 
     /// Set the positions of the joints
     ///
-    /// `FixedJoints` are ignored. the input number must be equal with `dof()`
+    /// `FixedJoints` are ignored. the input number must be equal with `num_positions()`
+    ///
+    /// The values for the mimic joints are ignored because they follow their parents,
+    /// see `set_active_joint_positions()`.
     pub fn set_joint_positions(&self, positions_vec: &[T]) -> Result<(), Error> {
-        if positions_vec.len() != self.dof {
+        if positions_vec.len() != self.num_positions {
             return Err(Error::SizeMismatchError {
                 input: positions_vec.len(),
-                required: self.dof,
+                required: self.num_positions,
             });
         }
-        for (joint, position) in self.movable_nodes.iter().zip(positions_vec.iter()) {
-            joint.set_joint_position(*position)?;
+        for (node, positions) in
+            split_values(&self.movable_nodes, positions_vec, JointType::num_positions)
+        {
+            node.set_joint_positions(positions)?;
         }
         Ok(())
     }
@@ -318,32 +844,454 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     ///
     /// This function is safe, in contrast to `set_joint_positions_unchecked`.
     pub fn set_joint_positions_clamped(&self, positions_vec: &[T]) {
-        for (joint, position) in self.movable_nodes.iter().zip(positions_vec.iter()) {
-            joint.set_joint_position_clamped(*position);
+        if positions_vec.len() != self.num_positions {
+            return;
+        }
+        for (node, positions) in
+            split_values(&self.movable_nodes, positions_vec, JointType::num_positions)
+        {
+            node.set_joint_positions_clamped(positions);
         }
     }
 
     /// Fast, but without check, dangerous `set_joint_positions`
     #[inline]
     pub fn set_joint_positions_unchecked(&self, positions_vec: &[T]) {
-        for (joint, position) in self.movable_nodes.iter().zip(positions_vec.iter()) {
-            joint.set_joint_position_unchecked(*position);
+        for (node, positions) in
+            split_values(&self.movable_nodes, positions_vec, JointType::num_positions)
+        {
+            node.set_joint_positions_unchecked(positions);
         }
     }
 
-    /// Update world_transform() of the joints
-    pub fn update_transforms(&self) -> Vec<Isometry3<T>> {
-        self.iter()
-            .map(|node| {
-                let parent_transform = node.parent_world_transform().expect("cache must exist");
-                let trans = parent_transform * node.joint().local_transform();
-                node.joint().set_world_transform(trans);
-                trans
+    /// Get the positions of the joints with their names
+    ///
+    /// The joints which do not have exactly one position (`Planar`, `Spherical` and
+    /// `Floating`) are not included, use `joint_positions()` for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    /// let named = chain.joint_positions_by_name();
+    /// assert_eq!(named.len(), 12);
+    /// assert_eq!(named[0], ("r_shoulder_yaw".to_owned(), 0.0));
+    /// ```
+    pub fn joint_positions_by_name(&self) -> Vec<(String, T)> {
+        self.iter_joints()
+            .filter_map(|joint| {
+                joint
+                    .joint_position()
+                    .map(|position| (joint.name.clone(), position))
             })
             .collect()
     }
 
+    /// Set the positions of the joints by their names
+    ///
+    /// Only the given joints (and their mimic children) are changed, and the values for
+    /// the mimic joints are ignored. `positions` can be `HashMap<String, T>` or any
+    /// iterator of the pairs.
+    ///
+    /// All the names and the limits, including the limits of the mimic children at the
+    /// coupled positions, are checked before changing any joint. If some of the names
+    /// are not found, it returns `UnknownJointNamesError` with all of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    /// let mut positions = HashMap::new();
+    /// positions.insert("l_elbow_pitch".to_owned(), 0.5);
+    /// positions.insert("r_elbow_pitch".to_owned(), -0.5);
+    /// chain.set_joint_positions_by_name(positions).unwrap();
+    /// assert_eq!(chain.find("l_elbow_pitch").unwrap().joint_position(), Some(0.5));
+    ///
+    /// let result = chain.set_joint_positions_by_name(vec![("l_elbow_pitch", 0.1), ("elbow", 0.2)]);
+    /// match result {
+    ///     Err(k::Error::UnknownJointNamesError { joint_names }) => assert_eq!(joint_names, vec!["elbow"]),
+    ///     _ => panic!("must be an error"),
+    /// }
+    /// // nothing is changed if it fails
+    /// assert_eq!(chain.find("l_elbow_pitch").unwrap().joint_position(), Some(0.5));
+    /// ```
+    pub fn set_joint_positions_by_name<I, S>(&self, positions: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: AsRef<str>,
+    {
+        let mut unknown_names = Vec::new();
+        let mut targets = Vec::new();
+        for (name, position) in positions {
+            match self.find(name.as_ref()) {
+                Some(node) => targets.push((node, position)),
+                None => unknown_names.push(name.as_ref().to_owned()),
+            }
+        }
+        if !unknown_names.is_empty() {
+            return Err(Error::UnknownJointNamesError {
+                joint_names: unknown_names,
+            });
+        }
+        // the mimic joints follow their parents, so their values are ignored
+        targets.retain(|(node, _)| node.mimic_parents().is_empty());
+        for (node, position) in &targets {
+            let joint = node.joint();
+            if !joint.is_movable() {
+                return Err(Error::SetToFixedError {
+                    joint_name: joint.name.clone(),
+                });
+            }
+            if joint.joint_positions().len() != 1 {
+                return Err(Error::SizeMismatchError {
+                    input: 1,
+                    required: joint.joint_positions().len(),
+                });
+            }
+            joint.check_joint_position(*position)?;
+        }
+        let mut new_positions = targets
+            .iter()
+            .map(|(node, position)| (node.as_ptr(), *position))
+            .collect::<HashMap<_, _>>();
+        let mut mimic_children = targets
+            .iter()
+            .flat_map(|(node, _)| node.mimic_children())
+            .collect::<Vec<_>>();
+        while let Some(child) = mimic_children.pop() {
+            if let Some(position) = coupled_position(&child, &new_positions) {
+                child.joint().check_joint_position(position)?;
+                // the grandchildren are checked with the new position of the child
+                new_positions.insert(child.as_ptr(), position);
+            }
+            mimic_children.extend(child.mimic_children());
+        }
+        for (node, position) in targets {
+            node.set_joint_position(position)?;
+        }
+        Ok(())
+    }
+
+    fn check_num_positions(&self, positions_vec: &[T]) -> Result<(), Error> {
+        check_size(positions_vec, self.num_positions)
+    }
+
+    fn check_dof(&self, values: &[T]) -> Result<(), Error> {
+        check_size(values, self.dof)
+    }
+
+    /// Interpolate the joint positions from `from` to `to` by `ratio` (0.0 to 1.0)
+    ///
+    /// `Continuous` joints move along the shortest path, they never spin the long way round.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let wheel = NodeBuilder::new()
+    ///     .joint_type(JointType::Continuous{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let arm = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .into_node();
+    /// arm.set_parent(&wheel);
+    /// let chain = Chain::<f64>::from_root(wheel);
+    /// let middle = chain.interpolate_joint_positions(&[3.0, 3.0], &[-3.0, -3.0], 0.5).unwrap();
+    /// assert!((middle[0].abs() - std::f64::consts::PI).abs() < 1e-10);
+    /// assert!(middle[1].abs() < 1e-10);
+    /// ```
+    pub fn interpolate_joint_positions(
+        &self,
+        from: &[T],
+        to: &[T],
+        ratio: T,
+    ) -> Result<Vec<T>, Error> {
+        self.check_num_positions(from)?;
+        self.check_num_positions(to)?;
+        let mut positions = Vec::with_capacity(self.num_positions);
+        for ((node, from), (_, to)) in
+            split_values(&self.movable_nodes, from, JointType::num_positions)
+                .into_iter()
+                .zip(split_values(
+                    &self.movable_nodes,
+                    to,
+                    JointType::num_positions,
+                ))
+        {
+            positions.extend(node.joint().interpolate_positions(from, to, ratio));
+        }
+        Ok(positions)
+    }
+
+    /// Move the joint positions by the joint velocities in the unit time
+    ///
+    /// The length of `positions` is `num_positions()` and the length of `velocities` is `dof()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Floating)
+    ///     .into_node();
+    /// let chain = Chain::<f64>::from_root(l0);
+    /// let positions = chain
+    ///     .integrate_joint_positions(&chain.joint_positions(), &[1.0, 0.0, 0.0, 0.0, 0.0, 0.1])
+    ///     .unwrap();
+    /// assert_eq!(positions.len(), 7);
+    /// assert_eq!(positions[0], 1.0);
+    /// ```
+    pub fn integrate_joint_positions(
+        &self,
+        positions_vec: &[T],
+        velocities_vec: &[T],
+    ) -> Result<Vec<T>, Error> {
+        self.check_num_positions(positions_vec)?;
+        self.check_dof(velocities_vec)?;
+        let mut output = Vec::with_capacity(self.num_positions);
+        for ((node, positions), (_, velocities)) in
+            split_values(&self.movable_nodes, positions_vec, JointType::num_positions)
+                .into_iter()
+                .zip(split_values(
+                    &self.movable_nodes,
+                    velocities_vec,
+                    JointType::dof,
+                ))
+        {
+            output.extend(node.joint().integrate_positions(positions, velocities));
+        }
+        Ok(output)
+    }
+
+    /// Get the velocities of the joints
+    ///
+    /// `FixedJoint` is ignored. the length is the same with `dof()`
+    pub fn joint_velocities(&self) -> Vec<T> {
+        let mut velocities = Vec::with_capacity(self.dof);
+        for joint in self.iter_joints() {
+            velocities.extend_from_slice(joint.joint_velocities());
+        }
+        velocities
+    }
+
+    /// Set the velocities of the joints
+    ///
+    /// `FixedJoints` are ignored. the input number must be equal with `dof()`.
+    /// It returns Err if any velocity is over the velocity limit of the joint.
+    pub fn set_joint_velocities(&self, velocities_vec: &[T]) -> Result<(), Error> {
+        self.check_dof(velocities_vec)?;
+        for (node, velocities) in split_values(&self.movable_nodes, velocities_vec, JointType::dof)
+        {
+            node.set_joint_velocities(velocities)?;
+        }
+        Ok(())
+    }
+
+    /// Check the velocities against the velocity limits of the joints
+    ///
+    /// The error contains the name of the first joint which violates the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .name("j0")
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .velocity_limit(Some(1.0))
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .name("j1")
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .velocity_limit(Some(0.5))
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let chain = Chain::<f64>::from_root(l0);
+    /// assert!(chain.check_joint_velocities(&[1.0, -0.5]).is_ok());
+    /// match chain.check_joint_velocities(&[1.0, -0.8]) {
+    ///     Err(Error::VelocityOutOfLimitError { joint_name, .. }) => assert_eq!(joint_name, "j1"),
+    ///     _ => panic!("must be error"),
+    /// }
+    /// ```
+    pub fn check_joint_velocities(&self, velocities_vec: &[T]) -> Result<(), Error> {
+        self.check_dof(velocities_vec)?;
+        for (node, velocities) in split_values(&self.movable_nodes, velocities_vec, JointType::dof)
+        {
+            let joint = node.joint();
+            for velocity in velocities {
+                joint.check_joint_velocity(*velocity)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the efforts (forces or torques) against the effort limits of the joints
+    ///
+    /// The error contains the name of the first joint which violates the limit.
+    pub fn check_joint_efforts(&self, efforts_vec: &[T]) -> Result<(), Error> {
+        self.check_dof(efforts_vec)?;
+        for (node, efforts) in split_values(&self.movable_nodes, efforts_vec, JointType::dof) {
+            let joint = node.joint();
+            for effort in efforts {
+                joint.check_joint_effort(*effort)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the trajectory against the position and velocity limits of the joints
+    ///
+    /// `trajectory` is the sequence of the joint positions sampled with `time_step`.
+    /// The velocities are calculated by the difference of the neighbor points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .name("j0")
+    ///     .joint_type(JointType::Rotational{axis: Vector3::y_axis()})
+    ///     .limits(Some((-1.0..=1.0).into()))
+    ///     .velocity_limit(Some(1.0))
+    ///     .into_node();
+    /// let chain = Chain::<f64>::from_root(l0);
+    /// assert!(chain.check_trajectory(&[vec![0.0], vec![0.05], vec![0.1]], 0.1).is_ok());
+    /// // too fast
+    /// assert!(chain.check_trajectory(&[vec![0.0], vec![0.5]], 0.1).is_err());
+    /// // out of position limit
+    /// assert!(chain.check_trajectory(&[vec![1.0], vec![1.05]], 0.1).is_err());
+    /// ```
+    pub fn check_trajectory(&self, trajectory: &[Vec<T>], time_step: T) -> Result<(), Error> {
+        for positions_vec in trajectory {
+            self.check_num_positions(positions_vec)?;
+            for (node, positions) in
+                split_values(&self.movable_nodes, positions_vec, JointType::num_positions)
+            {
+                if let [position] = positions {
+                    node.joint().check_joint_position(*position)?;
+                }
+            }
+        }
+        for points in trajectory.windows(2) {
+            for ((node, prev), (_, next)) in
+                split_values(&self.movable_nodes, &points[0], JointType::num_positions)
+                    .into_iter()
+                    .zip(split_values(
+                        &self.movable_nodes,
+                        &points[1],
+                        JointType::num_positions,
+                    ))
+            {
+                let joint = node.joint();
+                for diff in joint.positions_difference(prev, next) {
+                    joint.check_joint_velocity(diff / time_step)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Update world_transform() of the joints
+    ///
+    /// Only the nodes which moved (or whose ancestors moved) since the last update are
+    /// recalculated. The caches of the other nodes are kept valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let root = NodeBuilder::new().into_node();
+    /// let left = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let right = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// left.set_parent(&root);
+    /// right.set_parent(&root);
+    /// let tree = Chain::<f64>::from_root(root);
+    /// tree.update_transforms();
+    /// left.set_joint_position(0.5).unwrap();
+    /// // only the moved branch needs the update
+    /// assert!(left.world_transform().is_none());
+    /// assert!(right.world_transform().is_some());
+    /// tree.update_transforms();
+    /// assert!(left.world_transform().is_some());
+    /// ```
+    pub fn update_transforms(&self) -> Vec<Isometry3<T>> {
+        self.iter().map(update_world_transform).collect()
+    }
+
+    /// Update world_transform() of the joints and write them into `transforms`
+    ///
+    /// It is the same as `update_transforms()` without allocation, the length of
+    /// `transforms` must be the number of the nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Linear{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .translation(Translation3::new(1.0, 0.0, 0.0))
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let tree = Chain::<f64>::from_root(l0);
+    /// let mut transforms = vec![Isometry3::identity(); 2];
+    /// tree.set_joint_positions(&[0.5]).unwrap();
+    /// tree.update_transforms_into(&mut transforms).unwrap();
+    /// assert_eq!(transforms[1].translation.vector, Vector3::new(1.0, 0.0, 0.5));
+    /// assert!(tree.update_transforms_into(&mut transforms[..1]).is_err());
+    /// ```
+    pub fn update_transforms_into(&self, transforms: &mut [Isometry3<T>]) -> Result<(), Error> {
+        check_size(transforms, self.nodes.len())?;
+        for (node, transform) in self.iter().zip(transforms.iter_mut()) {
+            *transform = update_world_transform(node);
+        }
+        Ok(())
+    }
+
+    /// Update world_transform() of the joints without returning them
+    pub(crate) fn update_transform_caches(&self) {
+        self.iter().for_each(|node| {
+            update_world_transform(node);
+        });
+    }
+
     /// Update world_velocity() of the joints
+    ///
+    /// It returns the velocities of the end of the joints in the world frame.
+    /// `translation` is the linear velocity and `rotation` is the angular velocity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .translation(Translation3::new(1.0, 0.0, 0.0))
+    ///     .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let chain = Chain::<f64>::from_root(l0);
+    /// chain.set_joint_velocities(&[1.0, 0.5]).unwrap();
+    /// let velocities = chain.update_velocities();
+    /// assert!((velocities[1].translation - Vector3::new(0.5, 1.0, 0.0)).norm() < 0.001);
+    /// assert!((velocities[1].rotation - Vector3::new(0.0, 0.0, 1.0)).norm() < 0.001);
+    /// ```
     pub fn update_velocities(&self) -> Vec<Velocity<T>> {
         self.update_transforms();
         self.iter()
@@ -354,43 +1302,133 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
                 let parent_velocity = node
                     .parent_world_velocity()
                     .expect("velocity cache must exist");
-                let velocity = match node.joint().joint_type {
-                    JointType::Fixed => parent_velocity,
-                    JointType::Rotational { axis } => {
-                        let parent = node.parent().expect("parent must exist");
-                        let parent_vel = parent.joint().origin().translation.vector;
-                        Velocity::from_parts(
-                            parent_velocity.translation
-                                + parent_velocity.rotation.cross(
-                                    &(parent_transform.rotation.to_rotation_matrix() * parent_vel),
-                                ),
-                            parent_velocity.rotation
-                                + node
-                                    .world_transform()
-                                    .expect("cache must exist")
-                                    .rotation
-                                    .to_rotation_matrix()
-                                    * (axis.into_inner() * node.joint().joint_velocity().unwrap()),
-                        )
-                    }
-                    JointType::Linear { axis } => Velocity::from_parts(
-                        parent_velocity.translation
-                            + node
-                                .world_transform()
-                                .expect("cache must exist")
-                                .rotation
-                                .to_rotation_matrix()
-                                * (axis.into_inner() * node.joint().joint_velocity().unwrap()),
-                        // TODO: Is this true??
-                        parent_velocity.rotation,
-                    ),
-                };
-                node.joint().set_world_velocity(velocity);
+                let joint = node.joint();
+                let trans = joint.world_transform().expect("cache must exist");
+                let rotation = (parent_transform * joint.origin()).rotation;
+                let relative = joint.relative_velocity();
+                let r = trans.translation.vector - parent_transform.translation.vector;
+                let velocity = Velocity::from_parts(
+                    parent_velocity.translation
+                        + parent_velocity.rotation.cross(&r)
+                        + rotation * relative.linear,
+                    parent_velocity.rotation + rotation * relative.angular,
+                );
+                joint.set_world_velocity(velocity);
                 velocity
             })
             .collect()
     }
 
+    /// Get the accelerations of the joints
+    ///
+    /// `FixedJoint` is ignored. the length is the same with `dof()`
+    pub fn joint_accelerations(&self) -> Vec<T> {
+        let mut accelerations = Vec::with_capacity(self.dof);
+        for joint in self.iter_joints() {
+            accelerations.extend_from_slice(joint.joint_accelerations());
+        }
+        accelerations
+    }
+
+    /// Set the accelerations of the joints
+    ///
+    /// `FixedJoints` are ignored. the input number must be equal with `dof()`
+    pub fn set_joint_accelerations(&self, accelerations_vec: &[T]) -> Result<(), Error> {
+        self.check_dof(accelerations_vec)?;
+        for (node, accelerations) in
+            split_values(&self.movable_nodes, accelerations_vec, JointType::dof)
+        {
+            node.set_joint_accelerations(accelerations)?;
+        }
+        Ok(())
+    }
+
+    /// Update world_acceleration() of the joints
+    ///
+    /// It returns the accelerations of the end of the joints in the world frame.
+    /// `angular` is the angular acceleration and `linear` is the linear acceleration.
+    /// The root is not accelerated (gravity is not included).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .translation(Translation3::new(1.0, 0.0, 0.0))
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let chain = Chain::<f64>::from_root(l0);
+    /// chain.set_joint_velocities(&[2.0]).unwrap();
+    /// let accelerations = chain.update_accelerations();
+    /// // centripetal acceleration
+    /// assert!((accelerations[1].linear - Vector3::new(-4.0, 0.0, 0.0)).norm() < 0.001);
+    /// ```
+    pub fn update_accelerations(&self) -> Vec<Motion<T>> {
+        self.update_velocities();
+        self.iter()
+            .map(|node| {
+                let parent_transform = node
+                    .parent_world_transform()
+                    .expect("transform cache must exist");
+                let parent_velocity = node
+                    .parent_world_velocity()
+                    .expect("velocity cache must exist");
+                let parent_acceleration = node
+                    .parent_world_acceleration()
+                    .expect("acceleration cache must exist");
+                let joint = node.joint();
+                let trans = joint.world_transform().expect("cache must exist");
+                let rotation = (parent_transform * joint.origin()).rotation;
+                let relative_velocity = joint.relative_velocity();
+                let relative_acceleration = joint.relative_acceleration();
+                let r = trans.translation.vector - parent_transform.translation.vector;
+                let w = parent_velocity.rotation;
+                let w_rel = rotation * relative_velocity.angular;
+                let v_rel = rotation * relative_velocity.linear;
+                let acceleration = Motion::from_parts(
+                    parent_acceleration.linear
+                        + parent_acceleration.angular.cross(&r)
+                        + w.cross(&w.cross(&r))
+                        + w.cross(&v_rel) * na::convert::<_, T>(2.0)
+                        + rotation * relative_acceleration.linear,
+                    parent_acceleration.angular
+                        + w.cross(&w_rel)
+                        + rotation * relative_acceleration.angular,
+                );
+                joint.set_world_acceleration(acceleration);
+                acceleration
+            })
+            .collect()
+    }
+
+    /// Total mass of the links and the payloads
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::link::*;
+    ///
+    /// let l0 = NodeBuilder::<f64>::new().into_node();
+    /// let l1 = NodeBuilder::new().into_node();
+    /// l0.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(1.0)).finalize()));
+    /// l1.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(3.0)).finalize()));
+    /// l1.set_parent(&l0);
+    /// let chain = Chain::from_root(l0);
+    /// assert_eq!(chain.mass(), 4.0);
+    /// l1.set_payload(Some(Inertial::from_mass(0.5)));
+    /// assert_eq!(chain.mass(), 4.5);
+    /// ```
+    pub fn mass(&self) -> T {
+        self.iter()
+            .filter_map(|node| node.inertia())
+            .fold(T::zero(), |mass, inertia| mass + inertia.mass)
+    }
+
     /// Update transforms of the links
     pub fn update_link_transforms(&self) {
         self.update_transforms();
@@ -413,49 +1451,94 @@ impl<T: RealField + SubsetOf<f64>> Chain<T> {
     }
 }
 
-impl<T> Clone for Chain<T>
+impl<T, B> Chain<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
-    fn clone(&self) -> Self {
+    /// Copy all the nodes into another backend
+    ///
+    /// The nodes are copied as `clone()`, the copy does not share them with this chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let chain = Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    /// let rc_chain: RcChain<f64> = chain.to_backend();
+    /// rc_chain.set_joint_positions(&chain.joint_positions()).unwrap();
+    /// assert_eq!(rc_chain.update_transforms(), chain.update_transforms());
+    /// ```
+    pub fn to_backend<C: NodeBackend<T>>(&self) -> Chain<T, C> {
         // first node must be root
         if self.nodes.is_empty() {
-            return Self {
-                nodes: vec![],
-                movable_nodes: vec![],
-                dof: 0,
-            };
+            return Chain::empty();
         }
         assert!(self.nodes[0].is_root());
         // Clone everything
-        let mut new_nodes = self
+        let new_nodes = self
             .nodes
             .iter()
             .map(|n| {
-                let node = Node::new(n.joint().clone());
+                let node = Node::<T, C>::from_joint(n.joint().clone());
                 node.set_link(n.link().clone());
+                node.set_payload(n.payload());
                 node
             })
             .collect::<Vec<_>>();
+        let index_of = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.as_ptr(), i))
+            .collect::<HashMap<_, _>>();
+        let new_node = |node: &Node<T, B>| new_nodes[index_of[&node.as_ptr()]].clone();
         // Connect to new nodes
-        for i in 0..new_nodes.len() {
-            if let Some(p) = self.nodes[i].parent() {
-                // get index of p
-                let parent_index = self.nodes.iter().position(|x| *x == p).unwrap();
-                new_nodes[i].set_parent(&new_nodes[parent_index]);
+        for (node, new) in self.nodes.iter().zip(&new_nodes) {
+            if let Some(p) = node.parent() {
+                new.set_parent(&new_nodes[index_of[&p.as_ptr()]]);
             }
-            if let Some(m) = self.nodes[i].mimic_parent() {
-                let parent_index = self.nodes.iter().position(|x| *x == m).unwrap();
-                new_nodes[i].set_mimic_parent(
-                    &new_nodes[parent_index],
-                    self.nodes[i].lock().mimic.clone().unwrap(),
-                );
+            if let Some(coupling) = node.coupling() {
+                let new_parents = node
+                    .mimic_parents()
+                    .iter()
+                    .map(|m| &new_nodes[index_of[&m.as_ptr()]])
+                    .collect::<Vec<_>>();
+                new.set_coupling_arc(&new_parents, coupling);
             }
         }
+        let passive_nodes = self.passive_nodes.iter().map(new_node).collect();
+        let loop_closures = self
+            .loop_closures
+            .iter()
+            .map(|closure| LoopClosure {
+                node1: new_node(&closure.node1),
+                frame1: closure.frame1,
+                node2: new_node(&closure.node2),
+                frame2: closure.frame2,
+                closure_type: closure.closure_type,
+            })
+            .collect();
         //
         // first node must be root
         assert!(new_nodes[0].is_root());
-        Chain::from_root(new_nodes.remove(0))
+        // keep the order of the nodes, it can differ from `from_root()` after `attach()`
+        let mut chain = Chain::from_nodes(new_nodes);
+        chain.passive_nodes = passive_nodes;
+        chain.loop_closures = loop_closures;
+        chain.transmissions = self.transmissions.clone();
+        chain
+    }
+}
+
+impl<T, B> Clone for Chain<T, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    fn clone(&self) -> Self {
+        self.to_backend()
     }
 }
 
@@ -463,19 +1546,26 @@ where
 /// Kinematic chain without any branch.
 ///
 /// All joints are connected sequentially.
-pub struct SerialChain<T: RealField> {
-    inner: Chain<T>,
+pub struct SerialChain<T: RealField, B: NodeBackend<T> = ArcMutexBackend> {
+    inner: Chain<T, B>,
 }
 
-impl<T> SerialChain<T>
+/// `Chain` with `RcRefCellBackend`, it is faster than `Chain` but not `Send`
+pub type RcChain<T> = Chain<T, RcRefCellBackend>;
+
+/// `SerialChain` with `RcRefCellBackend`
+pub type RcSerialChain<T> = SerialChain<T, RcRefCellBackend>;
+
+impl<T, B> SerialChain<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
     /// Convert Chain into SerialChain without any check
     ///
     /// If the input Chain has any branches it causes serious bugs.
     ///
-    pub fn new_unchecked(inner: Chain<T>) -> Self {
+    pub fn new_unchecked(inner: Chain<T, B>) -> Self {
         Self { inner }
     }
     /// Convert Chain into SerialChain
@@ -499,7 +1589,7 @@ where
     /// let chain = k::Chain::from_root(node0);
     /// assert!(k::SerialChain::try_new(chain).is_none());
     /// ```
-    pub fn try_new(inner: Chain<T>) -> Option<Self> {
+    pub fn try_new(inner: Chain<T, B>) -> Option<Self> {
         {
             let num = inner.iter().count();
             for node in inner.iter().take(num - 1) {
@@ -518,13 +1608,13 @@ where
     /// let node = k::NodeBuilder::<f32>::new().into_node();
     /// let s_chain = k::SerialChain::from_end(&node);
     /// ```
-    pub fn from_end(end_joint: &Node<T>) -> SerialChain<T> {
+    pub fn from_end(end_joint: &Node<T, B>) -> SerialChain<T, B> {
         SerialChain {
             inner: Chain::from_end(end_joint),
         }
     }
     /// Safely unwrap and returns inner `Chain` instance
-    pub fn unwrap(self) -> Chain<T> {
+    pub fn unwrap(self) -> Chain<T, B> {
         self.inner
     }
     /// Calculate transform of the end joint
@@ -533,11 +1623,19 @@ where
             trans * joint.joint().local_transform()
         })
     }
+    /// Calculate velocity of the end joint in the world frame
+    pub fn end_velocity(&self) -> Velocity<T> {
+        *self
+            .update_velocities()
+            .last()
+            .expect("SerialChain must have nodes")
+    }
 }
 
-impl<T> Clone for SerialChain<T>
+impl<T, B> Clone for SerialChain<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
     fn clone(&self) -> Self {
         Self {
@@ -546,20 +1644,22 @@ where
     }
 }
 
-impl<T> Display for SerialChain<T>
+impl<T, B> Display for SerialChain<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.inner.fmt(f)
     }
 }
 
-impl<T> Deref for SerialChain<T>
+impl<T, B> Deref for SerialChain<T, B>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    type Target = Chain<T>;
+    type Target = Chain<T, B>;
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
@@ -697,7 +1797,7 @@ fn test_mimic() {
     joint2.set_parent(&joint1);
     joint2.set_mimic_parent(&joint1, Mimic::new(2.0, 0.5));
 
-    let arm = Chain::from_root(joint0);
+    let arm = Chain::<f64>::from_root(joint0);
 
     assert_eq!(arm.joint_positions().len(), 3);
     println!("{:?}", arm.joint_positions());
@@ -707,4 +1807,14 @@ fn test_mimic() {
     assert_eq!(positions[0], 0.1);
     assert_eq!(positions[1], 0.2);
     assert_eq!(positions[2], 0.9);
+
+    assert_eq!(arm.dof(), 3);
+    assert_eq!(arm.active_dof(), 2);
+    assert_eq!(arm.active_joint_positions(), vec![0.1, 0.2]);
+    assert!(arm.set_active_joint_positions(&positions).is_err());
+    arm.set_active_joint_positions(&[0.3, -0.1]).unwrap();
+    let positions = arm.joint_positions();
+    assert_eq!(positions[0], 0.3);
+    assert_eq!(positions[1], -0.1);
+    assert!((positions[2] - 0.3).abs() < 1e-6);
 }
@@ -0,0 +1,349 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::{DMatrix, DVector, Isometry3, RealField, Translation3, UnitQuaternion, Vector3};
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+
+use super::backend::*;
+use super::chain::*;
+use super::errors::*;
+use super::funcs::*;
+use super::ik::*;
+use super::node::*;
+
+/// What is constrained by `LoopClosure`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClosureType {
+    /// The two points are coincident (like a ball joint)
+    Point,
+    /// The two frames are the same (like a rigid connection)
+    Pose,
+}
+
+/// Constraint which closes a kinematic loop between two nodes
+///
+/// `Chain` is a tree, so a closed loop (parallel link, four-bar linkage) is
+/// expressed by cutting it and connecting the two cut ends by this constraint.
+/// Add it by `Chain::add_loop_closure()` and solve it by `LoopClosureSolver`.
+#[derive(Debug, Clone)]
+pub struct LoopClosure<T: RealField, B: NodeBackend<T> = ArcMutexBackend> {
+    /// First node
+    pub node1: Node<T, B>,
+    /// Frame in the first node
+    pub frame1: Isometry3<T>,
+    /// Second node
+    pub node2: Node<T, B>,
+    /// Frame in the second node
+    pub frame2: Isometry3<T>,
+    /// What is constrained
+    pub closure_type: ClosureType,
+}
+
+impl<T, B> LoopClosure<T, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    /// Constraint to make `point1` in `node1` and `point2` in `node2` coincident
+    pub fn new_point(
+        node1: &Node<T, B>,
+        point1: Vector3<T>,
+        node2: &Node<T, B>,
+        point2: Vector3<T>,
+    ) -> Self {
+        Self {
+            node1: node1.clone(),
+            frame1: Isometry3::from_parts(Translation3::from(point1), UnitQuaternion::identity()),
+            node2: node2.clone(),
+            frame2: Isometry3::from_parts(Translation3::from(point2), UnitQuaternion::identity()),
+            closure_type: ClosureType::Point,
+        }
+    }
+
+    /// Constraint to make `frame1` in `node1` and `frame2` in `node2` the same
+    pub fn new_pose(
+        node1: &Node<T, B>,
+        frame1: Isometry3<T>,
+        node2: &Node<T, B>,
+        frame2: Isometry3<T>,
+    ) -> Self {
+        Self {
+            node1: node1.clone(),
+            frame1,
+            node2: node2.clone(),
+            frame2,
+            closure_type: ClosureType::Pose,
+        }
+    }
+
+    /// Number of the constrained coordinates
+    pub fn dimension(&self) -> usize {
+        match self.closure_type {
+            ClosureType::Point => 3,
+            ClosureType::Pose => 6,
+        }
+    }
+
+    fn world_frames(&self) -> (Isometry3<T>, Isometry3<T>) {
+        (
+            world_transform_of(&self.node1) * self.frame1,
+            world_transform_of(&self.node2) * self.frame2,
+        )
+    }
+
+    /// Error of the constraint in the world frame, zero if the loop is closed
+    ///
+    /// It is `[translation, rotation]` from the first frame to the second frame,
+    /// only the translation for `ClosureType::Point`.
+    /// The cached world transforms are used if `Chain::update_transforms()` is called.
+    pub fn error(&self) -> DVector<T> {
+        let (world1, world2) = self.world_frames();
+        let diff = -calc_pose_diff(&world1, &world2);
+        DVector::from_iterator(
+            self.dimension(),
+            diff.iter().take(self.dimension()).cloned(),
+        )
+    }
+
+    /// Jacobian of `error()`
+    pub(crate) fn jacobian(&self, columns: &[(Node<T, B>, usize)], dof: usize) -> DMatrix<T> {
+        let (world1, world2) = self.world_frames();
+        let jacobi = node_jacobian(&self.node2, &world2.translation.vector, columns, dof)
+            - node_jacobian(&self.node1, &world1.translation.vector, columns, dof);
+        jacobi.rows(0, self.dimension()).into_owned()
+    }
+}
+
+/// World transform of the node, calculated from its ancestors if the cache is cleared
+fn world_transform_of<T, B>(node: &Node<T, B>) -> Isometry3<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    node.world_transform().unwrap_or_else(|| {
+        node.iter_ancestors()
+            .fold(Isometry3::identity(), |trans, ancestor| {
+                ancestor.joint().local_transform() * trans
+            })
+    })
+}
+
+/// Solver to close the kinematic loops by moving the passive joints
+///
+/// The passive joints are set by `Chain::set_passive_nodes()`.
+/// The joint positions are clamped by `Joint::clamp_limits` as `JacobianIKSolver`.
+#[derive(Debug, Clone)]
+pub struct LoopClosureSolver<T: RealField> {
+    /// The loop is closed if the norm of the errors is smaller than this
+    pub allowable_error: T,
+    /// Max number of the Newton iterations
+    pub num_max_try: usize,
+}
+
+impl<T> LoopClosureSolver<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    /// Create the solver
+    pub fn new(allowable_error: T, num_max_try: usize) -> Self {
+        Self {
+            allowable_error,
+            num_max_try,
+        }
+    }
+
+    /// Move the passive joints to close all the loops of the chain
+    ///
+    /// The active joints are not moved. The positions are restored if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let root = NodeBuilder::new().into_node();
+    /// let active = NodeBuilder::new()
+    ///     .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+    ///     .into_node();
+    /// let passive = NodeBuilder::new()
+    ///     .translation(Translation3::new(0.0, 1.0, 0.0))
+    ///     .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+    ///     .into_node();
+    /// active.set_parent(&root);
+    /// passive.set_parent(&root);
+    /// let mut chain = Chain::<f64>::from_root(root);
+    /// chain.set_passive_nodes(&[&passive]);
+    /// // the end of the passive joint is connected to the active joint
+    /// chain.add_loop_closure(LoopClosure::new_point(
+    ///     &active,
+    ///     Vector3::new(0.0, 1.0, 0.0),
+    ///     &passive,
+    ///     Vector3::zeros(),
+    /// ));
+    /// assert_eq!(chain.active_dof(), 1);
+    /// active.set_joint_position(0.3).unwrap();
+    /// LoopClosureSolver::default().solve(&chain).unwrap();
+    /// assert!((passive.joint_position().unwrap() - 0.3).abs() < 1e-6);
+    /// ```
+    pub fn solve<B: NodeBackend<T>>(&self, chain: &Chain<T, B>) -> Result<(), Error> {
+        let closures = chain.loop_closures();
+        self.solve_newton(chain, chain.passive_nodes().to_vec(), |columns, dof| {
+            stack_closures(closures, columns, dof)
+        })
+    }
+
+    /// Set the positions of the active joints and close the loops
+    ///
+    /// It is the forward kinematics of the closed chain. The length of `positions` is
+    /// `Chain::num_active_positions()`. All the positions are restored if it fails.
+    pub fn set_active_joint_positions<B: NodeBackend<T>>(
+        &self,
+        chain: &Chain<T, B>,
+        positions: &[T],
+    ) -> Result<(), Error> {
+        let orig_positions = chain.joint_positions();
+        let result = chain
+            .set_active_joint_positions(positions)
+            .and_then(|_| self.solve(chain));
+        if result.is_err() {
+            chain.set_joint_positions(&orig_positions)?;
+        }
+        result
+    }
+
+    /// Inverse kinematics of the closed chain
+    ///
+    /// Both the active and the passive joints are moved to move `end` to `target_pose`
+    /// with keeping the loops closed. The positions are restored if it fails.
+    pub fn solve_ik<B: NodeBackend<T>>(
+        &self,
+        chain: &Chain<T, B>,
+        end: &Node<T, B>,
+        target_pose: &Isometry3<T>,
+        constraints: &Constraints,
+    ) -> Result<(), Error> {
+        let constraints_array = constraints_to_bool_array(*constraints);
+        let closures = chain.loop_closures();
+        let mut nodes = chain.active_nodes();
+        nodes.extend(chain.passive_nodes().iter().cloned());
+        self.solve_newton(chain, nodes, |columns, dof| {
+            let t_n = end.world_transform().expect("cache must exist");
+            let full_jacobi = node_jacobian(end, &t_n.translation.vector, columns, dof);
+            let rows = (0..6)
+                .filter(|i| constraints_array[*i])
+                .map(|i| full_jacobi.row(i))
+                .collect::<Vec<_>>();
+            let (closure_diff, closure_jacobi) = stack_closures(closures, columns, dof);
+            let target_diff = calc_pose_diff_with_constraints(target_pose, &t_n, constraints_array);
+            let mut diff = DVector::zeros(rows.len() + closure_diff.len());
+            diff.rows_mut(0, rows.len()).copy_from(&target_diff);
+            diff.rows_mut(rows.len(), closure_diff.len())
+                .copy_from(&closure_diff);
+            let mut jacobi = DMatrix::zeros(diff.len(), dof);
+            for (r, row) in rows.iter().enumerate() {
+                jacobi.row_mut(r).copy_from(row);
+            }
+            jacobi
+                .rows_mut(rows.len(), closure_diff.len())
+                .copy_from(&closure_jacobi);
+            (diff, jacobi)
+        })
+    }
+
+    /// Move `nodes` by Newton method until the norm of the diff becomes small
+    ///
+    /// `diff_and_jacobian` returns the diff to be added and its jacobian.
+    fn solve_newton<B, F>(
+        &self,
+        chain: &Chain<T, B>,
+        nodes: Vec<Node<T, B>>,
+        diff_and_jacobian: F,
+    ) -> Result<(), Error>
+    where
+        B: NodeBackend<T>,
+        F: Fn(&[(Node<T, B>, usize)], usize) -> (DVector<T>, DMatrix<T>),
+    {
+        const EPS: f64 = 0.0001;
+        let orig_positions = chain.joint_positions();
+        let (columns, dof) = jacobian_columns(nodes);
+        let mut error = T::zero();
+        for _ in 0..=self.num_max_try {
+            chain.update_transforms();
+            let (diff, jacobi) = diff_and_jacobian(&columns, dof);
+            error = diff.norm();
+            if error < self.allowable_error {
+                return Ok(());
+            }
+            if dof == 0 {
+                break;
+            }
+            let d_q = jacobi
+                .svd(true, true)
+                .solve(&diff, na::convert(EPS))
+                .map_err(|_| Error::InverseMatrixError)?;
+            for (node, col) in &columns {
+                let positions = {
+                    let joint = node.joint();
+                    let node_dof = joint.joint_type.dof();
+                    joint.integrate_positions(
+                        joint.joint_positions(),
+                        &d_q.as_slice()[*col..*col + node_dof],
+                    )
+                };
+                node.set_joint_positions_clamped(&positions);
+            }
+        }
+        chain.set_joint_positions(&orig_positions)?;
+        Err(Error::LoopClosureError {
+            num_tried: self.num_max_try,
+            error: na::try_convert(error).unwrap_or_default(),
+        })
+    }
+}
+
+impl<T> Default for LoopClosureSolver<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    fn default() -> Self {
+        Self::new(na::convert(1.0e-8), 100)
+    }
+}
+
+/// The diffs to close all the loops and their jacobians
+fn stack_closures<T, B>(
+    closures: &[LoopClosure<T, B>],
+    columns: &[(Node<T, B>, usize)],
+    dof: usize,
+) -> (DVector<T>, DMatrix<T>)
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    let dimension = closures.iter().map(LoopClosure::dimension).sum::<usize>();
+    let mut diff = DVector::zeros(dimension);
+    let mut jacobi = DMatrix::zeros(dimension, dof);
+    let mut row = 0;
+    for closure in closures {
+        let len = closure.dimension();
+        diff.rows_mut(row, len).copy_from(&-closure.error());
+        jacobi
+            .rows_mut(row, len)
+            .copy_from(&closure.jacobian(columns, dof));
+        row += len;
+    }
+    (diff, jacobi)
+}
@@ -13,19 +13,29 @@
   See the License for the specific language governing permissions and
   limitations under the License.
 */
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
 use nalgebra as na;
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 /// The reason of joint error
-#[derive(Debug, Clone, Error)]
+///
+/// Without the `std` feature it does not implement `std::error::Error` and the
+/// `Display` shows the same as `Debug`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum Error {
     /// Failed to set joint angle because the input is out of range or it is fixed joint
-    #[error(
-        "joint: {} is out of limit: {}, limit = {} <=> {}",
-        joint_name,
-        position,
-        max_limit,
-        min_limit
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "joint: {} is out of limit: {}, limit = {} <=> {}",
+            joint_name,
+            position,
+            max_limit,
+            min_limit
+        )
     )]
     OutOfLimitError {
         /// name of the joint
@@ -37,13 +47,50 @@ pub enum Error {
         /// min limit
         min_limit: f64,
     },
-    #[error("joint {} is fixed joint but the position is set", joint_name)]
+    /// Failed to set joint velocity because the input exceeds the velocity limit
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "joint: {} velocity {} exceeds the limit {}",
+            joint_name,
+            velocity,
+            limit
+        )
+    )]
+    VelocityOutOfLimitError {
+        /// name of the joint
+        joint_name: String,
+        /// target velocity
+        velocity: f64,
+        /// limit of the absolute velocity
+        limit: f64,
+    },
+    /// Effort (force or torque) exceeds the effort limit of the joint
+    #[cfg_attr(
+        feature = "std",
+        error("joint: {} effort {} exceeds the limit {}", joint_name, effort, limit)
+    )]
+    EffortOutOfLimitError {
+        /// name of the joint
+        joint_name: String,
+        /// target effort
+        effort: f64,
+        /// limit of the absolute effort
+        limit: f64,
+    },
+    #[cfg_attr(
+        feature = "std",
+        error("joint {} is fixed joint but the position is set", joint_name)
+    )]
     SetToFixedError {
         /// name of the joint
         joint_name: String,
     },
     /// Gave invalid size of vec as input
-    #[error("size mismatch input = {}, required = {}", input, required)]
+    #[cfg_attr(
+        feature = "std",
+        error("size mismatch input = {}, required = {}", input, required)
+    )]
     SizeMismatchError {
         /// size of input
         input: usize,
@@ -51,30 +98,131 @@ pub enum Error {
         required: usize,
     },
     /// Error about mimic
-    #[error("mimic error from {} to {}", from, to)]
+    #[cfg_attr(feature = "std", error("mimic error from {} to {}", from, to))]
     MimicError {
         /// tried to copy from `from`
         from: String,
         /// tried to copy to `to`
         to: String,
     },
-    #[error(
-        "ik solve not converged tried {} times, position diff = {}, rotation diff = {}",
-        num_tried,
-        position_diff,
-        rotation_diff
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "ik solve not converged tried {} times, position diff = {}, rotation diff = {}",
+            num_tried,
+            position_diff,
+            rotation_diff
+        )
     )]
     NotConvergedError {
         num_tried: usize,
         position_diff: na::Vector3<f64>,
         rotation_diff: na::Vector3<f64>,
     },
-    #[error("inverse matrix error")]
+    #[cfg_attr(feature = "std", error("inverse matrix error"))]
     InverseMatrixError,
-    #[error(
-        "ik precondition error: input Dof={}, must be greater than {}",
-        dof,
-        necessary_dof
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "ik precondition error: input Dof={}, must be greater than {}",
+            dof,
+            necessary_dof
+        )
     )]
     PreconditionError { dof: usize, necessary_dof: usize },
+    /// The joint is not found in the chain
+    #[cfg_attr(feature = "std", error("joint {} is not found", joint_name))]
+    JointNotFoundError {
+        /// name of the joint
+        joint_name: String,
+    },
+    /// Some of the joints are not found in the chain
+    #[cfg_attr(feature = "std", error("joints {:?} are not found", joint_names))]
+    UnknownJointNamesError {
+        /// names of all the joints which are not found
+        joint_names: Vec<String>,
+    },
+    /// The node is already in the chain
+    #[cfg_attr(feature = "std", error("joint {} is already in the chain", joint_name))]
+    SharedNodeError {
+        /// name of the joint
+        joint_name: String,
+    },
+    /// The name of the joint is already used in the chain
+    #[cfg_attr(feature = "std", error("joint name {} is already used", joint_name))]
+    DuplicateJointNameError {
+        /// name of the joint
+        joint_name: String,
+    },
+    /// The name of the link is already used in the chain
+    #[cfg_attr(feature = "std", error("link name {} is already used", link_name))]
+    DuplicateLinkNameError {
+        /// name of the link
+        link_name: String,
+    },
+    /// The root of the chain can not be detached because the chain can not be empty
+    #[cfg_attr(
+        feature = "std",
+        error("root joint {} can not be detached", joint_name)
+    )]
+    DetachRootError {
+        /// name of the root joint
+        joint_name: String,
+    },
+    /// The mimic joints are coupled in a circle, so their positions can not be calculated
+    #[cfg_attr(feature = "std", error("coupling of joint {} is circular", joint_name))]
+    CircularCouplingError {
+        /// name of a joint in the circle
+        joint_name: String,
+    },
+    /// The parent of the joint does not come before the joint
+    #[cfg_attr(
+        feature = "std",
+        error("parent {} of joint {} must come before the joint", parent, index)
+    )]
+    InvalidParentError {
+        /// index of the joint
+        index: usize,
+        /// index of the parent
+        parent: usize,
+    },
+    /// The index of the node is not in the model
+    #[cfg_attr(
+        feature = "std",
+        error("node index {} is out of range, len = {}", index, len)
+    )]
+    NodeIndexOutOfRangeError {
+        /// index of the node
+        index: usize,
+        /// number of the nodes
+        len: usize,
+    },
+    /// The mechanical reduction of the transmission is zero
+    #[cfg_attr(
+        feature = "std",
+        error("mechanical reduction of the transmission for {} is zero", joint_name)
+    )]
+    ZeroMechanicalReductionError {
+        /// name of the joint
+        joint_name: String,
+    },
+    /// Failed to parse the transmissions in URDF
+    #[cfg_attr(feature = "std", error("failed to parse transmissions: {}", message))]
+    TransmissionParseError {
+        /// reason of the error
+        message: String,
+    },
+    /// Failed to close the kinematic loops
+    #[cfg_attr(
+        feature = "std",
+        error("loop closure not solved tried {} times, error = {}", num_tried, error)
+    )]
+    LoopClosureError { num_tried: usize, error: f64 },
+}
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
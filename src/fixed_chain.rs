@@ -0,0 +1,160 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::{RealField, SMatrix, SVector};
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::ops::Deref;
+
+use super::backend::*;
+use super::chain::*;
+use super::errors::*;
+use super::funcs::*;
+
+/// Serial chain whose degree of freedom is fixed at compile time
+///
+/// The positions are `SVector<T, N>` and the jacobian is `SMatrix<T, 6, N>`,
+/// they are allocated on the stack.
+///
+/// It derefs to `SerialChain`, so the forward kinematics and the IK solvers
+/// (`InverseKinematicsSolver`) can be used as is.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+/// use nalgebra::Vector2;
+///
+/// let l0 = NodeBuilder::new()
+///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+///     .into_node();
+/// let l1 = NodeBuilder::new()
+///     .translation(Translation3::new(1.0, 0.0, 0.0))
+///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+///     .into_node();
+/// let l2 = NodeBuilder::new()
+///     .translation(Translation3::new(1.0, 0.0, 0.0))
+///     .into_node();
+/// l1.set_parent(&l0);
+/// l2.set_parent(&l1);
+/// let arm = FixedSerialChain::<f64, 2>::try_new(SerialChain::from_end(&l2)).unwrap();
+/// arm.set_joint_positions(&Vector2::new(0.0, std::f64::consts::FRAC_PI_2)).unwrap();
+/// let jacobi = arm.jacobian();
+/// assert!((jacobi[(0, 0)] + 1.0).abs() < 1e-10);
+/// assert!((jacobi[(1, 1)] - 0.0).abs() < 1e-10);
+/// assert_eq!(arm.joint_positions()[1], std::f64::consts::FRAC_PI_2);
+/// ```
+#[derive(Debug)]
+pub struct FixedSerialChain<T: RealField, const N: usize, B: NodeBackend<T> = ArcMutexBackend> {
+    inner: SerialChain<T, B>,
+}
+
+impl<T, const N: usize, B> FixedSerialChain<T, N, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    /// Convert `SerialChain` checking the degree of freedom
+    ///
+    /// All the movable joints must be active and have one position, and the number of
+    /// them must be `N`.
+    pub fn try_new(inner: SerialChain<T, B>) -> Result<Self, Error> {
+        for input in &[inner.dof(), inner.num_positions(), inner.active_dof()] {
+            if *input != N {
+                return Err(Error::SizeMismatchError {
+                    input: *input,
+                    required: N,
+                });
+            }
+        }
+        Ok(Self { inner })
+    }
+    /// Unwrap and returns inner `SerialChain` instance
+    pub fn unwrap(self) -> SerialChain<T, B> {
+        self.inner
+    }
+    /// Get the positions of the joints
+    pub fn joint_positions(&self) -> SVector<T, N> {
+        let mut positions = SVector::zeros();
+        for (i, joint) in self.inner.iter_joints().enumerate() {
+            positions[i] = joint.joint_positions()[0];
+        }
+        positions
+    }
+    /// Set the positions of the joints
+    ///
+    /// It returns `Err` if the positions are out of the limits.
+    pub fn set_joint_positions(&self, positions: &SVector<T, N>) -> Result<(), Error> {
+        self.inner.set_joint_positions(positions.as_slice())
+    }
+    /// Set the positions of the joints with clamping them by the limits
+    pub fn set_joint_positions_clamped(&self, positions: &SVector<T, N>) {
+        self.inner.set_joint_positions_clamped(positions.as_slice())
+    }
+    /// Calculate the jacobian of the end
+    ///
+    /// It is the same as `jacobian()` without any heap allocation.
+    pub fn jacobian(&self) -> SMatrix<T, 6, N> {
+        let mut jacobi = SMatrix::zeros();
+        fill_jacobian(&self.inner, &mut jacobi);
+        jacobi
+    }
+}
+
+impl<T, const N: usize, B> TryFrom<SerialChain<T, B>> for FixedSerialChain<T, N, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    type Error = Error;
+    fn try_from(inner: SerialChain<T, B>) -> Result<Self, Error> {
+        Self::try_new(inner)
+    }
+}
+
+impl<T, const N: usize, B> Clone for FixedSerialChain<T, N, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T, const N: usize, B> Display for FixedSerialChain<T, N, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T, const N: usize, B> Deref for FixedSerialChain<T, N, B>
+where
+    T: RealField,
+    B: NodeBackend<T>,
+{
+    type Target = SerialChain<T, B>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
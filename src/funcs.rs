@@ -1,44 +1,280 @@
+use super::backend::*;
 use super::chain::*;
+use super::errors::*;
 use super::joint::*;
-use na::{DMatrix, RealField, Vector3};
+use super::node::*;
+use log::warn;
+use na::storage::StorageMut;
+use na::{DMatrix, Dim, Matrix, RealField, Vector3, Vector6};
 use nalgebra as na;
 use simba::scalar::SubsetOf;
 
 /// Calculate Jacobian of the serial chain (manipulator).
-pub fn jacobian<T>(arm: &SerialChain<T>) -> DMatrix<T>
+///
+/// The columns are for the active joints (`Chain::active_dof()`). The effects of the
+/// mimic joints are added to the columns of the joints which drive them
+/// (see `Node::set_coupling`). The passive joints of the closed loops are ignored,
+/// use `LoopClosureSolver` for them.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+///
+/// let l0 = NodeBuilder::new()
+///     .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+///     .into_node();
+/// let l1 = NodeBuilder::new()
+///     .joint_type(JointType::Linear{axis: Vector3::y_axis()})
+///     .into_node();
+/// l1.set_parent(&l0);
+/// l1.set_mimic_parent(&l0, k::joint::Mimic::new(2.0, 0.0));
+/// let arm = SerialChain::from_end(&l1);
+/// let jacobi = jacobian(&arm);
+/// assert_eq!(jacobi.shape(), (6, 1));
+/// // moving l0 moves l1 twice
+/// assert_eq!(jacobi[(0, 0)], 1.0);
+/// assert_eq!(jacobi[(1, 0)], 2.0);
+/// ```
+pub fn jacobian<T, B>(arm: &SerialChain<T, B>) -> DMatrix<T>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
-    let dof = arm.dof();
-    let t_n = arm.end_transform();
-    arm.update_transforms();
-    let p_n = t_n.translation;
-    let jacobi_vec = arm
-        .iter_joints()
-        .map(|joint| {
-            let t_i = joint.world_transform().unwrap();
-            match joint.joint_type {
-                JointType::Linear { axis } => {
-                    let p_i = t_i.rotation * axis;
-                    [p_i[0], p_i[1], p_i[2], na::zero(), na::zero(), na::zero()]
-                }
-                JointType::Rotational { axis } => {
-                    let p_i = t_i.translation;
-                    let a_i = t_i.rotation * axis;
-                    let dp_i = a_i.cross(&(p_n.vector - p_i.vector));
-                    [dp_i[0], dp_i[1], dp_i[2], a_i[0], a_i[1], a_i[2]]
+    let mut jacobi = DMatrix::zeros(6, arm.active_dof());
+    jacobian_into(arm, &mut jacobi).expect("the size must be correct");
+    jacobi
+}
+
+/// Calculate Jacobian of the serial chain into `jacobi`
+///
+/// It is the same as `jacobian()` without allocation if there are no mimic joints.
+/// The shape of `jacobi` must be `(6, arm.active_dof())`.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+/// use nalgebra::DMatrix;
+///
+/// let l0 = NodeBuilder::new()
+///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+///     .into_node();
+/// let l1 = NodeBuilder::new()
+///     .translation(Translation3::new(1.0, 0.0, 0.0))
+///     .into_node();
+/// l1.set_parent(&l0);
+/// let arm = SerialChain::from_end(&l1);
+/// let mut jacobi = DMatrix::zeros(6, 1);
+/// jacobian_into(&arm, &mut jacobi).unwrap();
+/// assert_eq!(jacobi[(1, 0)], 1.0);
+/// assert_eq!(jacobi[(5, 0)], 1.0);
+/// assert!(jacobian_into(&arm, &mut DMatrix::zeros(6, 2)).is_err());
+/// // the rows are checked separately from the columns
+/// match jacobian_into(&arm, &mut DMatrix::zeros(5, 1)) {
+///     Err(Error::SizeMismatchError { input, required }) => assert_eq!((input, required), (5, 6)),
+///     _ => panic!("the shape must be checked"),
+/// }
+/// ```
+pub fn jacobian_into<T, B>(arm: &SerialChain<T, B>, jacobi: &mut DMatrix<T>) -> Result<(), Error>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    let dof = arm.active_dof();
+    for (input, required) in &[(jacobi.nrows(), 6), (jacobi.ncols(), dof)] {
+        if input != required {
+            return Err(Error::SizeMismatchError {
+                input: *input,
+                required: *required,
+            });
+        }
+    }
+    fill_jacobian(arm, jacobi);
+    Ok(())
+}
+
+/// Write the jacobian of the serial chain into the matrix which has the correct shape
+///
+/// It is shared by `jacobian_into()` and the fixed size chains.
+pub(crate) fn fill_jacobian<T, B, R, C, S>(arm: &SerialChain<T, B>, jacobi: &mut Matrix<T, R, C, S>)
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+    R: Dim,
+    C: Dim,
+    S: StorageMut<T, R, C>,
+{
+    jacobi.fill(T::zero());
+    let p_n = arm.end_transform().translation.vector;
+    arm.update_transform_caches();
+    let column_of = |node: &Node<T, B>| {
+        let mut col = 0;
+        for active in arm.iter_active_nodes() {
+            if active == node {
+                return Some(col);
+            }
+            col += active.joint().joint_type.dof();
+        }
+        None
+    };
+    // the nodes of the serial chain are the ancestors of the end
+    let mut col = 0;
+    for node in arm.iter() {
+        let is_active = arm.is_active(node);
+        let is_mimic = node.is_mimic();
+        let mimic_column = {
+            let joint = node.joint();
+            if !joint.is_movable() {
+                continue;
+            }
+            if is_active {
+                for index in 0..joint.joint_type.dof() {
+                    set_column(jacobi, col + index, &jacobian_column(&joint, &p_n, index));
                 }
-                JointType::Fixed => panic!("impossible, bug of jacobian"),
+                col += joint.joint_type.dof();
+                continue;
             }
-        })
-        .collect::<Vec<_>>();
-    // Pi: a_i x (p_n - Pi)
+            if !is_mimic {
+                // passive joint
+                continue;
+            }
+            jacobian_column(&joint, &p_n, 0)
+        };
+        add_to_driving_columns(node, &mimic_column, T::one(), &column_of, jacobi);
+    }
+}
+
+fn set_column<T, R, C, S>(jacobi: &mut Matrix<T, R, C, S>, col: usize, column: &Vector6<T>)
+where
+    T: RealField,
+    R: Dim,
+    C: Dim,
+    S: StorageMut<T, R, C>,
+{
+    for (r, value) in column.iter().enumerate() {
+        jacobi[(r, col)] = *value;
+    }
+}
+
+/// Pairs of the node and the index of its first column in the jacobian
+pub(crate) type JacobianColumns<T, B> = Vec<(Node<T, B>, usize)>;
+
+/// `JacobianColumns` of the nodes, and the number of columns
+pub(crate) fn jacobian_columns<T, B>(nodes: Vec<Node<T, B>>) -> (JacobianColumns<T, B>, usize)
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    let mut columns = Vec::with_capacity(nodes.len());
+    let mut dof = 0;
+    for node in nodes {
+        let node_dof = node.joint().joint_type.dof();
+        columns.push((node, dof));
+        dof += node_dof;
+    }
+    (columns, dof)
+}
+
+/// Column of the jacobian of the point `p_n` for the `index`-th motion of the joint
+///
+/// The world transform of the joint must be updated before calling this.
+fn jacobian_column<T>(joint: &Joint<T>, p_n: &Vector3<T>, index: usize) -> Vector6<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    let t_i = joint.world_transform().unwrap();
+    let p_i = t_i.translation;
+    // rotation of the origin frame of the joint
+    let rotation = t_i.rotation * joint.joint_transform().rotation.inverse();
+    let motion = joint.motion_subspace(index);
     // wi: a_i
-    DMatrix::from_fn(6, dof, |r, c| jacobi_vec[c][r])
+    let w_i = rotation * motion.angular;
+    // Pi: v_i + a_i x (p_n - Pi)
+    let dp_i = rotation * motion.linear + w_i.cross(&(p_n - p_i.vector));
+    Vector6::new(dp_i[0], dp_i[1], dp_i[2], w_i[0], w_i[1], w_i[2])
+}
+
+/// Jacobian of the point `p_n` (in the world frame) which is fixed to `end`
+///
+/// Only the joints in `columns` have columns, the mimic joints are added to the joints
+/// which drive them and the others are ignored.
+/// The world transforms of the nodes must be updated before calling this.
+pub(crate) fn node_jacobian<T, B>(
+    end: &Node<T, B>,
+    p_n: &Vector3<T>,
+    columns: &[(Node<T, B>, usize)],
+    dof: usize,
+) -> DMatrix<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    let column_of =
+        |node: &Node<T, B>| columns.iter().find(|(n, _)| n == node).map(|(_, col)| *col);
+    let mut jacobi = DMatrix::zeros(6, dof);
+    for node in end.iter_ancestors() {
+        let motions = {
+            let joint = node.joint();
+            if !joint.is_movable() {
+                continue;
+            }
+            (0..joint.joint_type.dof())
+                .map(|index| jacobian_column(&joint, p_n, index))
+                .collect::<Vec<_>>()
+        };
+        match column_of(&node) {
+            Some(col) => {
+                for (i, motion) in motions.iter().enumerate() {
+                    set_column(&mut jacobi, col + i, motion);
+                }
+            }
+            // mimic joint: the position is a scalar function of the parents
+            None => add_to_driving_columns(&node, &motions[0], T::one(), &column_of, &mut jacobi),
+        }
+    }
+    jacobi
+}
+
+/// Add `column * weight` to the columns of the joints which drive `node`
+///
+/// `column_of` returns the index of the column of the driving joint.
+/// The mimic joints whose parents have been dropped are skipped with a warning.
+pub(crate) fn add_to_driving_columns<T, B, F, R, C, S>(
+    node: &Node<T, B>,
+    column: &Vector6<T>,
+    weight: T,
+    column_of: &F,
+    jacobi: &mut Matrix<T, R, C, S>,
+) where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+    F: Fn(&Node<T, B>) -> Option<usize>,
+    R: Dim,
+    C: Dim,
+    S: StorageMut<T, R, C>,
+{
+    match node.coupling_derivatives() {
+        Ok(Some(derivatives)) => {
+            for (parent, derivative) in derivatives {
+                add_to_driving_columns(&parent, column, weight * derivative, column_of, jacobi);
+            }
+        }
+        Err(err) => warn!("mimic joint {} is not added to the jacobian: {}", node, err),
+        Ok(None) => {
+            if let Some(col) = column_of(node) {
+                for (r, value) in column.iter().enumerate() {
+                    jacobi[(r, col)] += *value * weight;
+                }
+            }
+        }
+    }
 }
 
 /// Calculate the center of mass of the chain
 ///
+/// The payloads of the nodes (`Node::set_payload`) are also included.
+///
 /// ```
 /// use k::*;
 /// use k::link::*;
@@ -55,9 +291,10 @@ where
 /// let tree = Chain::from_root(j0);
 /// let com1 = center_of_mass(&tree);
 /// ```
-pub fn center_of_mass<T>(chain: &Chain<T>) -> Vector3<T>
+pub fn center_of_mass<T, B>(chain: &Chain<T, B>) -> Vector3<T>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
     let mut total_mass = T::zero();
     let mut com = Vector3::zeros();
@@ -65,10 +302,9 @@ where
     chain.update_transforms();
     chain.iter().for_each(|node| {
         if let Some(trans) = node.world_transform() {
-            if let Some(ref link) = *node.link() {
-                let inertia_trans = trans * link.inertial.origin().translation;
-                com += inertia_trans.translation.vector * link.inertial.mass;
-                total_mass += link.inertial.mass;
+            if let Some(inertia) = node.inertia() {
+                com += inertia.transform(&trans).com * inertia.mass;
+                total_mass += inertia.mass;
             }
         }
     });
@@ -114,3 +350,60 @@ fn test_update_center_of_mass() {
     assert_eq!(com2.y, 1.0);
     assert!((com2.z - 1.502066).abs() < 0.0001);
 }
+
+#[test]
+fn test_center_of_mass_with_payload() {
+    use super::joint::*;
+    use super::link::*;
+    use super::node::*;
+    use na::*;
+    let j0 = NodeBuilder::new()
+        .translation(Translation3::new(0.0, 0.0, 0.5))
+        .joint_type(JointType::Rotational {
+            axis: Vector3::z_axis(),
+        })
+        .into_node();
+    let j1 = NodeBuilder::new()
+        .translation(Translation3::new(1.0, 0.0, 0.0))
+        .joint_type(JointType::Rotational {
+            axis: Vector3::y_axis(),
+        })
+        .into_node();
+    j0.set_link(Some(
+        LinkBuilder::new()
+            .inertial(Inertial::new(
+                Isometry3::identity(),
+                2.0,
+                Matrix3::identity(),
+            ))
+            .finalize(),
+    ));
+    j1.set_link(Some(
+        LinkBuilder::new()
+            .inertial(Inertial::new(
+                Isometry3::identity(),
+                1.0,
+                Matrix3::identity(),
+            ))
+            .finalize(),
+    ));
+    j1.set_parent(&j0);
+    let tree = Chain::from_root(j0.clone());
+    tree.set_joint_positions(&[0.3, 0.5]).unwrap();
+    let com0 = center_of_mass(&tree);
+    // payload at 1.0 ahead of j1
+    let mut payload = Inertial::new(Isometry3::identity(), 1.0, Matrix3::identity() * 0.1);
+    payload.set_origin(Isometry3::translation(1.0, 0.0, 0.0));
+    j1.set_payload(Some(payload));
+    assert_eq!(tree.mass(), 4.0);
+    assert_eq!(j1.subtree_mass(), 2.0);
+    let com1 = center_of_mass(&tree);
+    let payload_position = j1.world_transform().unwrap() * Point3::new(1.0, 0.0, 0.0);
+    assert!((com1 - (com0 * 3.0 + payload_position.coords) / 4.0).norm() < 1e-10);
+    // composite inertia of the root is the same as the sum of all
+    let composite = j0
+        .composite_inertia()
+        .transform(&j0.world_transform().unwrap());
+    assert!((composite.mass - 4.0).abs() < 1e-10);
+    assert!((composite.com - com1).norm() < 1e-10);
+}
@@ -0,0 +1,113 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::RealField;
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+use std::sync::{Arc, Weak};
+
+use super::backend::*;
+use super::chain::*;
+use super::errors::*;
+use super::node::*;
+
+/// Handle of a joint which has one position
+///
+/// It is created by `Chain::joint_handle()` once, and then the position can be read
+/// and written without looking up the name.
+///
+/// # Examples
+///
+/// ```
+/// let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+/// let elbow = chain.joint_handle("l_elbow_pitch").unwrap();
+/// elbow.set_position(0.5).unwrap();
+/// assert_eq!(elbow.position(), 0.5);
+/// assert_eq!(chain.joint_positions()[elbow.position_index().unwrap()], 0.5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct JointHandle<T: RealField, B: NodeBackend<T> = ArcMutexBackend> {
+    node: Node<T, B>,
+    position_index: usize,
+    positions_layout: Weak<()>,
+}
+
+impl<T, B> JointHandle<T, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    /// The node of the joint
+    pub fn node(&self) -> &Node<T, B> {
+        &self.node
+    }
+    /// Index of the position in `Chain::joint_positions()`
+    ///
+    /// It returns `None` after `Chain::detach()` shifted the indices, or after the chain
+    /// is dropped. `Chain::attach()` keeps the indices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    /// let elbow = chain.joint_handle("l_elbow_pitch").unwrap();
+    /// assert_eq!(elbow.position_index(), Some(9));
+    /// let r_shoulder = chain.find("r_shoulder_yaw").unwrap().clone();
+    /// chain.detach(&r_shoulder).unwrap();
+    /// assert_eq!(elbow.position_index(), None);
+    /// let elbow = chain.joint_handle("l_elbow_pitch").unwrap();
+    /// assert_eq!(elbow.position_index(), Some(3));
+    /// ```
+    pub fn position_index(&self) -> Option<usize> {
+        if self.positions_layout.strong_count() == 0 {
+            return None;
+        }
+        Some(self.position_index)
+    }
+    /// The position of the joint
+    pub fn position(&self) -> T {
+        self.node.joint().joint_positions()[0]
+    }
+    /// Set the position of the joint, same as `Node::set_joint_position()`
+    pub fn set_position(&self, position: T) -> Result<(), Error> {
+        self.node.set_joint_position(position)
+    }
+    /// Set the position of the joint with clamping it by the limits
+    pub fn set_position_clamped(&self, position: T) {
+        self.node.set_joint_position_clamped(position)
+    }
+}
+
+impl<T, B> Chain<T, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    /// Create the handle of the joint
+    ///
+    /// It returns `None` if there is no joint of the name or the joint does not have
+    /// exactly one position. Create the handles again after `detach()`.
+    pub fn joint_handle(&self, joint_name: &str) -> Option<JointHandle<T, B>> {
+        let node = self.find(joint_name)?;
+        if node.joint().joint_type.num_positions() != 1 {
+            return None;
+        }
+        Some(JointHandle {
+            node: node.clone(),
+            position_index: self.position_offset(joint_name)?,
+            positions_layout: Arc::downgrade(self.positions_layout()),
+        })
+    }
+}
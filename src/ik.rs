@@ -17,12 +17,13 @@ use na::{DVector, Isometry3, RealField, Vector3, Vector6};
 use nalgebra as na;
 use simba::scalar::SubsetOf;
 
+use super::backend::*;
 use super::chain::*;
 use super::errors::*;
 use super::funcs::*;
 
 /// From 'Humanoid Robot (Kajita)' P.64
-fn calc_pose_diff<T>(a: &Isometry3<T>, b: &Isometry3<T>) -> Vector6<T>
+pub(crate) fn calc_pose_diff<T>(a: &Isometry3<T>, b: &Isometry3<T>) -> Vector6<T>
 where
     T: RealField,
 {
@@ -33,7 +34,7 @@ where
     )
 }
 
-fn calc_pose_diff_with_constraints<T>(
+pub(crate) fn calc_pose_diff_with_constraints<T>(
     a: &Isometry3<T>,
     b: &Isometry3<T>,
     constraints_array: [bool; 6],
@@ -91,7 +92,7 @@ impl Default for Constraints {
     }
 }
 
-fn constraints_to_bool_array(constraints: Constraints) -> [bool; 6] {
+pub(crate) fn constraints_to_bool_array(constraints: Constraints) -> [bool; 6] {
     let mut arr = [true; 6];
     arr[0] = constraints.position_x;
     arr[1] = constraints.position_y;
@@ -103,24 +104,30 @@ fn constraints_to_bool_array(constraints: Constraints) -> [bool; 6] {
 }
 
 /// IK solver
-pub trait InverseKinematicsSolver<T>
+///
+/// `B` is the backend of the nodes of the arm.
+pub trait InverseKinematicsSolver<T, B = ArcMutexBackend>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
     /// Move the end transform of the `arm` to `target_pose`
-    fn solve(&self, arm: &SerialChain<T>, target_pose: &Isometry3<T>) -> Result<(), Error> {
+    fn solve(&self, arm: &SerialChain<T, B>, target_pose: &Isometry3<T>) -> Result<(), Error> {
         self.solve_with_constraints(arm, target_pose, &Constraints::default())
     }
     /// Move the end transform of the `arm` to `target_pose` with constraints
     fn solve_with_constraints(
         &self,
-        arm: &SerialChain<T>,
+        arm: &SerialChain<T, B>,
         target_pose: &Isometry3<T>,
         constraints: &Constraints,
     ) -> Result<(), Error>;
 }
 
 /// Inverse Kinematics Solver using Jacobian matrix
+///
+/// The joint positions are kept in the soft limits of the safety controllers
+/// (`Joint::clamp_limits`) while solving.
 pub struct JacobianIKSolver<T: RealField> {
     /// If the distance is smaller than this value, it is reached.
     pub allowable_target_distance: T,
@@ -163,6 +170,9 @@ where
     }
     /// Set a null space function for redundant manipulator.
     ///
+    /// The input is the positions of the active joints (`Chain::active_joint_positions()`) and
+    /// the length of the output must be `active_dof()` of the arm.
+    ///
     /// # Examples
     ///
     /// ```
@@ -183,25 +193,29 @@ where
         self.nullspace_function = None;
     }
 
-    fn add_positions_with_multiplier(&self, input: &[T], add_values: &[T]) -> Vec<T> {
-        input
+    fn add_positions_with_multiplier<B: NodeBackend<T>>(
+        &self,
+        arm: &SerialChain<T, B>,
+        input: &[T],
+        add_values: &[T],
+    ) -> Result<Vec<T>, Error> {
+        let velocities = add_values
             .iter()
-            .zip(add_values.iter())
-            .map(|(ang, add)| *ang + self.jacobian_multiplier * *add)
-            .collect()
+            .map(|add| self.jacobian_multiplier * *add)
+            .collect::<Vec<_>>();
+        arm.integrate_active_joint_positions(input, &velocities)
     }
 
-    fn solve_one_loop_with_constraints(
+    fn solve_one_loop_with_constraints<B: NodeBackend<T>>(
         &self,
-        arm: &SerialChain<T>,
+        arm: &SerialChain<T, B>,
         target_pose: &Isometry3<T>,
         constraints_array: [bool; 6],
     ) -> Result<DVector<T>, Error> {
-        let orig_positions = arm.joint_positions();
-        let dof = orig_positions.len();
+        let dof = arm.active_dof();
         let t_n = arm.end_transform();
         let err = calc_pose_diff_with_constraints(target_pose, &t_n, constraints_array);
-        let orig_positions = arm.joint_positions();
+        let orig_positions = arm.active_joint_positions();
         let mut jacobi = jacobian(arm);
         let use_dof = constraints_array.iter().filter(|x| **x).count();
         let mut removed_count = 0;
@@ -220,29 +234,31 @@ where
                     let d_q = jacobi_inv.clone() * err
                         + (na::DMatrix::identity(dof, dof) - jacobi_inv * jacobi)
                             * na::DVector::from_vec(f(&orig_positions));
-                    self.add_positions_with_multiplier(&orig_positions, d_q.as_slice())
+                    self.add_positions_with_multiplier(arm, &orig_positions, d_q.as_slice())?
                 }
                 None => self.add_positions_with_multiplier(
+                    arm,
                     &orig_positions,
                     jacobi
                         .svd(true, true)
                         .solve(&err, na::convert(EPS))
                         .unwrap() // TODO
                         .as_slice(),
-                ),
+                )?,
             }
         } else {
             // normal inverse matrix
             self.add_positions_with_multiplier(
+                arm,
                 &orig_positions,
                 jacobi
                     .lu()
                     .solve(&err)
                     .ok_or(Error::InverseMatrixError)?
                     .as_slice(),
-            )
+            )?
         };
-        arm.set_joint_positions_clamped(&positions_vec);
+        arm.set_active_joint_positions_clamped(&positions_vec);
         Ok(calc_pose_diff_with_constraints(
             target_pose,
             &arm.end_transform(),
@@ -250,18 +266,19 @@ where
         ))
     }
 
-    fn solve_with_constraints_internal(
+    fn solve_with_constraints_internal<B: NodeBackend<T>>(
         &self,
-        arm: &SerialChain<T>,
+        arm: &SerialChain<T, B>,
         target_pose: &Isometry3<T>,
         constraints: &Constraints,
     ) -> Result<(), Error> {
         let constraints_array = constraints_to_bool_array(*constraints);
         let orig_positions = arm.joint_positions();
         let use_dof = constraints_array.iter().filter(|x| **x).count();
-        if orig_positions.len() < use_dof {
+        let dof = arm.active_dof();
+        if dof < use_dof {
             return Err(Error::PreconditionError {
-                dof: orig_positions.len(),
+                dof,
                 necessary_dof: use_dof,
             });
         }
@@ -313,9 +330,10 @@ where
     (len_diff, rot_diff)
 }
 
-impl<T> InverseKinematicsSolver<T> for JacobianIKSolver<T>
+impl<T, B> InverseKinematicsSolver<T, B> for JacobianIKSolver<T>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
     /// Set joint positions of `arm` to reach the `target_pose`
     ///
@@ -350,7 +368,7 @@ where
     /// solver.solve(&arm, &target).unwrap();
     /// println!("solved positions={:?}", arm.joint_positions());
     /// ```
-    fn solve(&self, arm: &SerialChain<T>, target_pose: &Isometry3<T>) -> Result<(), Error> {
+    fn solve(&self, arm: &SerialChain<T, B>, target_pose: &Isometry3<T>) -> Result<(), Error> {
         self.solve_with_constraints(arm, target_pose, &Constraints::default())
     }
 
@@ -386,7 +404,7 @@ where
     /// ```
     fn solve_with_constraints(
         &self,
-        arm: &SerialChain<T>,
+        arm: &SerialChain<T, B>,
         target_pose: &Isometry3<T>,
         constraints: &Constraints,
     ) -> Result<(), Error> {
@@ -14,35 +14,39 @@
   limitations under the License.
 */
 //! Iterators to iterate descendants and ancestors
+use super::backend::*;
 use super::node::*;
 use nalgebra::RealField;
 use simba::scalar::SubsetOf;
 
 #[derive(Debug)]
 /// Iterator for parents
-pub struct Ancestors<T>
+pub struct Ancestors<T, B = ArcMutexBackend>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    parent: Option<Node<T>>,
+    parent: Option<Node<T, B>>,
 }
 
-impl<T> Ancestors<T>
+impl<T, B> Ancestors<T, B>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    pub fn new(parent: Option<Node<T>>) -> Self {
+    pub fn new(parent: Option<Node<T, B>>) -> Self {
         Self { parent }
     }
 }
 
-impl<T> Iterator for Ancestors<T>
+impl<T, B> Iterator for Ancestors<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
-    type Item = Node<T>;
+    type Item = Node<T, B>;
     #[allow(clippy::question_mark)]
-    fn next(&mut self) -> Option<Node<T>> {
+    fn next(&mut self) -> Option<Node<T, B>> {
         if self.parent.is_none() {
             return None;
         }
@@ -54,27 +58,30 @@ where
 
 #[derive(Debug)]
 /// Iterator for children
-pub struct Descendants<T>
+pub struct Descendants<T, B = ArcMutexBackend>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    stack: Vec<Node<T>>,
+    stack: Vec<Node<T, B>>,
 }
 
-impl<T> Descendants<T>
+impl<T, B> Descendants<T, B>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    pub fn new(stack: Vec<Node<T>>) -> Self {
+    pub fn new(stack: Vec<Node<T, B>>) -> Self {
         Self { stack }
     }
 }
 
-impl<T> Iterator for Descendants<T>
+impl<T, B> Iterator for Descendants<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
-    type Item = Node<T>;
+    type Item = Node<T, B>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let node = match self.stack.pop() {
@@ -18,10 +18,12 @@ mod joint;
 mod joint_type;
 mod mimic;
 mod range;
+mod safety_controller;
 mod velocity;
 
 pub use joint::*;
 pub use joint_type::*;
 pub use mimic::*;
 pub use range::*;
+pub use safety_controller::*;
 pub use velocity::*;
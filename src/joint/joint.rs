@@ -15,13 +15,17 @@
 */
 use super::joint_type::*;
 use super::range::*;
+use super::safety_controller::*;
 use super::velocity::*;
 use crate::errors::*;
-use na::{Isometry3, RealField, Translation3, UnitQuaternion};
+use crate::spatial::Motion;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, string::ToString, vec, vec::Vec};
+use core::cell::RefCell;
+use core::fmt::{self, Display};
+use na::{Isometry3, Quaternion, RealField, Translation3, Unit, UnitQuaternion, Vector3};
 use nalgebra as na;
 use simba::scalar::SubsetOf;
-use std::cell::RefCell;
-use std::fmt::{self, Display};
 
 /// Joint with type
 #[derive(Debug, Clone)]
@@ -30,18 +34,157 @@ pub struct Joint<T: RealField> {
     pub name: String,
     /// Type of this joint
     pub joint_type: JointType<T>,
-    /// position (angle) of this joint
-    position: T,
-    /// velocity of this joint
-    velocity: T,
+    /// positions (angle) of this joint, the length is `joint_type.num_positions()`
+    positions: Vec<T>,
+    /// velocities of this joint, the length is `joint_type.dof()`
+    velocities: Vec<T>,
+    /// accelerations of this joint, the length is `joint_type.dof()`
+    accelerations: Vec<T>,
     /// Limits of this joint
     pub limits: Option<Range<T>>,
+    /// Soft limits inside `limits`, which are used to clamp the position
+    pub safety_controller: Option<SafetyController<T>>,
+    /// Limit of the absolute velocity of this joint
+    pub velocity_limit: Option<T>,
+    /// Limit of the absolute effort (force or torque) of this joint
+    pub effort_limit: Option<T>,
     /// local origin transform of joint
     origin: Isometry3<T>,
     /// cache of world transform
     world_transform_cache: RefCell<Option<Isometry3<T>>>,
     /// cache of world velocity
     world_velocity_cache: RefCell<Option<Velocity<T>>>,
+    /// cache of world acceleration
+    world_acceleration_cache: RefCell<Option<Motion<T>>>,
+}
+
+/// Two axes in the plane perpendicular to `normal`, `(e1, e2, normal)` is right-handed
+fn plane_axes<T: RealField>(normal: &Unit<Vector3<T>>) -> (Vector3<T>, Vector3<T>) {
+    let reference = if normal.y.abs() < na::convert(0.9) {
+        Vector3::y()
+    } else {
+        Vector3::z()
+    };
+    let e1 = reference.cross(normal).normalize();
+    let e2 = normal.cross(&e1);
+    (e1, e2)
+}
+
+/// Quaternion from `[qx, qy, qz, qw]`
+fn quaternion_from_slice<T: RealField>(q: &[T]) -> UnitQuaternion<T> {
+    UnitQuaternion::from_quaternion(Quaternion::new(q[3], q[0], q[1], q[2]))
+}
+
+/// Wrap the angles and normalize the quaternions in the positions
+pub(crate) fn normalize_positions<T: RealField>(joint_type: &JointType<T>, positions: &mut [T]) {
+    match joint_type {
+        JointType::Continuous { .. } => positions[0] = wrap_angle(positions[0]),
+        JointType::Planar { .. } => positions[2] = wrap_angle(positions[2]),
+        _ => {}
+    }
+    if let Some(offset) = joint_type.quaternion_offset() {
+        let q = &mut positions[offset..offset + 4];
+        let quaternion = Quaternion::new(q[3], q[0], q[1], q[2]);
+        let quaternion = if quaternion.norm() > T::zero() {
+            UnitQuaternion::from_quaternion(quaternion)
+        } else {
+            UnitQuaternion::identity()
+        };
+        q.copy_from_slice(quaternion.coords.as_slice());
+    }
+}
+
+/// Transform of the joint by `positions`, without the origin
+pub(crate) fn joint_transform_of<T: RealField>(
+    joint_type: &JointType<T>,
+    positions: &[T],
+) -> Isometry3<T> {
+    let p = positions;
+    match *joint_type {
+        JointType::Fixed => Isometry3::identity(),
+        JointType::Rotational { axis } | JointType::Continuous { axis } => Isometry3::from_parts(
+            Translation3::new(T::zero(), T::zero(), T::zero()),
+            UnitQuaternion::from_axis_angle(&axis, p[0]),
+        ),
+        JointType::Linear { axis } => Isometry3::from_parts(
+            Translation3::from(axis.into_inner() * p[0]),
+            UnitQuaternion::identity(),
+        ),
+        JointType::Helical { axis, pitch } => Isometry3::from_parts(
+            Translation3::from(axis.into_inner() * (pitch * p[0])),
+            UnitQuaternion::from_axis_angle(&axis, p[0]),
+        ),
+        JointType::Planar { normal } => {
+            let (e1, e2) = plane_axes(&normal);
+            Isometry3::from_parts(
+                Translation3::from(e1 * p[0] + e2 * p[1]),
+                UnitQuaternion::from_axis_angle(&normal, p[2]),
+            )
+        }
+        JointType::Floating => Isometry3::from_parts(
+            Translation3::new(p[0], p[1], p[2]),
+            quaternion_from_slice(&p[3..7]),
+        ),
+        JointType::Spherical => Isometry3::from_parts(
+            Translation3::new(T::zero(), T::zero(), T::zero()),
+            quaternion_from_slice(p),
+        ),
+        JointType::Universal { axis1, axis2 } => Isometry3::from_parts(
+            Translation3::new(T::zero(), T::zero(), T::zero()),
+            UnitQuaternion::from_axis_angle(&axis1, p[0])
+                * UnitQuaternion::from_axis_angle(&axis2, p[1]),
+        ),
+    }
+}
+
+/// Motion of the end of the joint for the unit velocity of the `index`-th degree of freedom
+///
+/// The coordinates are the origin frame, see `Joint::motion_subspace`.
+pub(crate) fn motion_subspace_of<T: RealField>(
+    joint_type: &JointType<T>,
+    positions: &[T],
+    index: usize,
+) -> Motion<T> {
+    match *joint_type {
+        JointType::Fixed => panic!("fixed joint has no degree of freedom"),
+        JointType::Rotational { axis } | JointType::Continuous { axis } => {
+            Motion::from_parts(Vector3::zeros(), axis.into_inner())
+        }
+        JointType::Linear { axis } => Motion::from_parts(axis.into_inner(), Vector3::zeros()),
+        JointType::Helical { axis, pitch } => {
+            Motion::from_parts(axis.into_inner() * pitch, axis.into_inner())
+        }
+        JointType::Planar { normal } => {
+            let (e1, e2) = plane_axes(&normal);
+            match index {
+                0 => Motion::from_parts(e1, Vector3::zeros()),
+                1 => Motion::from_parts(e2, Vector3::zeros()),
+                _ => Motion::from_parts(Vector3::zeros(), normal.into_inner()),
+            }
+        }
+        JointType::Floating => {
+            let mut unit = Vector3::zeros();
+            unit[index % 3] = T::one();
+            if index < 3 {
+                Motion::from_parts(unit, Vector3::zeros())
+            } else {
+                Motion::from_parts(Vector3::zeros(), unit)
+            }
+        }
+        JointType::Spherical => {
+            let mut unit = Vector3::zeros();
+            unit[index] = T::one();
+            Motion::from_parts(Vector3::zeros(), unit)
+        }
+        JointType::Universal { axis1, axis2 } => match index {
+            0 => Motion::from_parts(Vector3::zeros(), axis1.into_inner()),
+            // the second axis is rotated by the first angle
+            _ => Motion::from_parts(
+                Vector3::zeros(),
+                UnitQuaternion::from_axis_angle(&axis1, positions[0]) * axis2.into_inner(),
+            ),
+        },
+    }
 }
 
 impl<T> Joint<T>
@@ -68,18 +211,24 @@ where
     pub fn new(name: &str, joint_type: JointType<T>) -> Joint<T> {
         Joint {
             name: name.to_string(),
+            positions: joint_type.neutral_positions(),
+            velocities: vec![T::zero(); joint_type.dof()],
+            accelerations: vec![T::zero(); joint_type.dof()],
             joint_type,
-            position: T::zero(),
-            velocity: T::zero(),
             limits: None,
+            safety_controller: None,
+            velocity_limit: None,
+            effort_limit: None,
             origin: Isometry3::identity(),
             world_transform_cache: RefCell::new(None),
             world_velocity_cache: RefCell::new(None),
+            world_acceleration_cache: RefCell::new(None),
         }
     }
     /// Set the position of the joint
     ///
     /// It returns Err if it is out of the limits, or this is fixed joint.
+    /// Use `set_joint_positions` for the joints which have multiple positions.
     ///
     /// # Examples
     ///
@@ -109,6 +258,77 @@ where
                 joint_name: self.name.to_string(),
             });
         }
+        if self.positions.len() != 1 {
+            return Err(Error::SizeMismatchError {
+                input: 1,
+                required: self.positions.len(),
+            });
+        }
+        let position = match self.joint_type {
+            JointType::Continuous { .. } => wrap_angle(position),
+            _ => position,
+        };
+        self.check_joint_position(position)?;
+        self.set_joint_position_unchecked(position);
+        Ok(())
+    }
+    /// Set the positions of the joint
+    ///
+    /// The length of `positions` must be `joint_type.num_positions()`.
+    /// The limits are checked only for the joints which have one position.
+    /// The quaternions of `Floating` and `Spherical` joints are normalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let mut planar = k::Joint::<f64>::new("p0", k::JointType::Planar { normal: na::Vector3::z_axis() });
+    /// planar.set_joint_positions(&[1.0, 2.0, 0.5]).unwrap();
+    /// assert_eq!(planar.joint_positions(), &[1.0, 2.0, 0.5]);
+    /// assert!(planar.joint_position().is_none());
+    /// assert!(planar.set_joint_positions(&[1.0]).is_err());
+    ///
+    /// let mut floating = k::Joint::<f64>::new("f0", k::JointType::Floating);
+    /// floating.set_joint_positions(&[1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 2.0]).unwrap();
+    /// assert_eq!(floating.joint_positions(), &[1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0]);
+    /// ```
+    pub fn set_joint_positions(&mut self, positions: &[T]) -> Result<(), Error> {
+        if !self.is_movable() {
+            return Err(Error::SetToFixedError {
+                joint_name: self.name.to_string(),
+            });
+        }
+        if positions.len() != self.positions.len() {
+            return Err(Error::SizeMismatchError {
+                input: positions.len(),
+                required: self.positions.len(),
+            });
+        }
+        if positions.len() == 1 {
+            return self.set_joint_position(positions[0]);
+        }
+        self.set_joint_positions_unchecked(positions);
+        Ok(())
+    }
+    /// Check if the position is in the limits of this joint
+    ///
+    /// It always succeeds if the joint has no limits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let mut rot = k::Joint::<f64>::new("r0", k::JointType::Rotational { axis: na::Vector3::y_axis() });
+    /// assert!(rot.check_joint_position(5.0).is_ok());
+    /// rot.limits = Some((-1.0..=1.0).into());
+    /// assert!(rot.check_joint_position(0.5).is_ok());
+    /// assert!(rot.check_joint_position(5.0).is_err());
+    /// ```
+    pub fn check_joint_position(&self, position: T) -> Result<(), Error> {
         if let Some(ref range) = self.limits {
             if !range.is_valid(position) {
                 return Err(Error::OutOfLimitError {
@@ -119,15 +339,102 @@ where
                 });
             }
         }
-        self.position = position;
-        // TODO: have to reset descendent `world_transform_cache`
-        self.world_transform_cache.replace(None);
-        self.world_velocity_cache.replace(None);
         Ok(())
     }
+    /// Check if the absolute value of the velocity is in the velocity limit
+    ///
+    /// It always succeeds if the joint has no velocity limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let mut rot = k::Joint::<f64>::new("r0", k::JointType::Rotational { axis: na::Vector3::y_axis() });
+    /// rot.velocity_limit = Some(1.0);
+    /// assert!(rot.check_joint_velocity(-1.0).is_ok());
+    /// assert!(rot.check_joint_velocity(1.5).is_err());
+    /// ```
+    pub fn check_joint_velocity(&self, velocity: T) -> Result<(), Error> {
+        if let Some(limit) = self.velocity_limit {
+            if velocity.abs() > limit {
+                return Err(Error::VelocityOutOfLimitError {
+                    joint_name: self.name.to_string(),
+                    velocity: na::try_convert(velocity).unwrap_or_default(),
+                    limit: na::try_convert(limit).unwrap_or_default(),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Check if the absolute value of the effort (force or torque) is in the effort limit
+    ///
+    /// It always succeeds if the joint has no effort limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let mut rot = k::Joint::<f64>::new("r0", k::JointType::Rotational { axis: na::Vector3::y_axis() });
+    /// rot.effort_limit = Some(10.0);
+    /// assert!(rot.check_joint_effort(-5.0).is_ok());
+    /// assert!(rot.check_joint_effort(-15.0).is_err());
+    /// ```
+    pub fn check_joint_effort(&self, effort: T) -> Result<(), Error> {
+        if let Some(limit) = self.effort_limit {
+            if effort.abs() > limit {
+                return Err(Error::EffortOutOfLimitError {
+                    joint_name: self.name.to_string(),
+                    effort: na::try_convert(effort).unwrap_or_default(),
+                    limit: na::try_convert(limit).unwrap_or_default(),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Limits to clamp the position
+    ///
+    /// They are the soft limits of `safety_controller` clamped by the hard `limits`,
+    /// or `limits` if there is no safety controller. If the soft limits are out of the
+    /// hard limits, the nearest hard limit is used for both ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut rot = k::Joint::<f64>::new("r0", k::JointType::Rotational { axis: k::Vector3::y_axis() });
+    /// rot.limits = Some((-1.0..=1.0).into());
+    /// rot.safety_controller = Some(k::joint::SafetyController::new((-2.0..=0.5).into(), 10.0, 1.0));
+    /// let limits = rot.clamp_limits().unwrap();
+    /// assert_eq!(limits.min, -1.0);
+    /// assert_eq!(limits.max, 0.5);
+    ///
+    /// // no overlap with the hard limits
+    /// rot.safety_controller = Some(k::joint::SafetyController::new((1.5..=2.0).into(), 10.0, 1.0));
+    /// let limits = rot.clamp_limits().unwrap();
+    /// assert_eq!(limits.min, 1.0);
+    /// assert_eq!(limits.max, 1.0);
+    /// ```
+    pub fn clamp_limits(&self) -> Option<Range<T>> {
+        let soft = match self.safety_controller {
+            Some(ref safety) => safety.soft_limits,
+            None => return self.limits,
+        };
+        match self.limits {
+            Some(hard) => {
+                let clamp = |value: T| value.max(hard.min).min(hard.max);
+                Some(Range::new(clamp(soft.min), clamp(soft.max)))
+            }
+            None => Some(soft),
+        }
+    }
     /// Set the clamped position of the joint
     ///
     /// It refers to the joint limit and clamps the argument. This function does nothing if this is fixed joint.
+    /// The soft limits are used if `safety_controller` exists (see `clamp_limits`), while
+    /// `set_joint_position` checks only the hard limits.
     ///
     /// # Examples
     ///
@@ -153,27 +460,179 @@ where
         if !self.is_movable() {
             return;
         }
-        let position_clamped = if let Some(ref range) = self.limits {
+        let position_clamped = if let Some(ref range) = self.clamp_limits() {
             range.clamp(position)
         } else {
             position
         };
         self.set_joint_position_unchecked(position_clamped);
     }
+    /// Set the clamped positions of the joint
+    ///
+    /// Only the joints which have one position are clamped.
+    pub fn set_joint_positions_clamped(&mut self, positions: &[T]) {
+        if positions.len() == 1 {
+            self.set_joint_position_clamped(positions[0]);
+        } else if self.is_movable() {
+            self.set_joint_positions_unchecked(positions);
+        }
+    }
+    /// Set the position without checking the limits
+    ///
+    /// It does nothing if this joint does not have exactly one position.
     pub fn set_joint_position_unchecked(&mut self, position: T) {
-        self.position = position;
-        // TODO: have to reset descendent `world_transform_cache`
+        if self.positions.len() != 1 {
+            return;
+        }
+        self.positions[0] = position;
+        normalize_positions(&self.joint_type, &mut self.positions);
+        self.clear_caches();
+    }
+    /// Set the positions without checking the limits
+    ///
+    /// It panics if the length of `positions` is not `joint_type.num_positions()`.
+    pub fn set_joint_positions_unchecked(&mut self, positions: &[T]) {
+        self.positions.copy_from_slice(positions);
+        normalize_positions(&self.joint_type, &mut self.positions);
+        self.clear_caches();
+    }
+    fn clear_caches(&self) {
+        // the caches of the descendants are cleared by `Node`
         self.world_transform_cache.replace(None);
         self.world_velocity_cache.replace(None);
+        self.world_acceleration_cache.replace(None);
+    }
+    /// Difference of the positions from `from` to `to`
+    ///
+    /// For `Continuous` joints, it is the shortest angle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let rot = k::Joint::<f64>::new("r0", k::JointType::Rotational { axis: na::Vector3::y_axis() });
+    /// assert!((rot.position_difference(3.0, -3.0) + 6.0).abs() < 1e-10);
+    /// let wheel = k::Joint::<f64>::new("w0", k::JointType::Continuous { axis: na::Vector3::y_axis() });
+    /// assert!((wheel.position_difference(3.0, -3.0) - (2.0 * std::f64::consts::PI - 6.0)).abs() < 1e-10);
+    /// ```
+    pub fn position_difference(&self, from: T, to: T) -> T {
+        match self.joint_type {
+            JointType::Continuous { .. } => wrap_angle(to - from),
+            _ => to - from,
+        }
+    }
+    /// Interpolate the positions from `from` to `to` by `ratio` (0.0 to 1.0)
+    ///
+    /// `Continuous` joints move along the shortest path.
+    pub fn interpolate_position(&self, from: T, to: T, ratio: T) -> T {
+        let position = from + self.position_difference(from, to) * ratio;
+        match self.joint_type {
+            JointType::Continuous { .. } => wrap_angle(position),
+            _ => position,
+        }
+    }
+    /// Difference of the positions from `from` to `to` as velocities
+    ///
+    /// The length of the output is `joint_type.dof()`. It is the velocities which moves
+    /// `from` to `to` in the unit time, so `integrate_positions(from, output)` is `to`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let floating = k::Joint::<f64>::new("f0", k::JointType::Floating);
+    /// let from = [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+    /// let to = floating.integrate_positions(&from, &[1.0, 2.0, 3.0, 0.0, 0.0, 0.5]);
+    /// let diff = floating.positions_difference(&from, &to);
+    /// assert_eq!(diff.len(), 6);
+    /// assert!((diff[2] - 3.0).abs() < 1e-10);
+    /// assert!((diff[5] - 0.5).abs() < 1e-10);
+    /// ```
+    pub fn positions_difference(&self, from: &[T], to: &[T]) -> Vec<T> {
+        if let Some(offset) = self.joint_type.quaternion_offset() {
+            // the rotation is the angular velocity in the origin frame
+            let rotation = quaternion_from_slice(&to[offset..offset + 4])
+                * quaternion_from_slice(&from[offset..offset + 4]).inverse();
+            let mut diff = from[..offset]
+                .iter()
+                .zip(to[..offset].iter())
+                .map(|(from, to)| *to - *from)
+                .collect::<Vec<_>>();
+            diff.extend_from_slice(rotation.scaled_axis().as_slice());
+            return diff;
+        }
+        match self.joint_type {
+            JointType::Planar { .. } => vec![
+                to[0] - from[0],
+                to[1] - from[1],
+                wrap_angle(to[2] - from[2]),
+            ],
+            _ => from
+                .iter()
+                .zip(to.iter())
+                .map(|(from, to)| self.position_difference(*from, *to))
+                .collect(),
+        }
+    }
+    /// Move the positions by the velocities in the unit time
+    ///
+    /// The length of `positions` is `joint_type.num_positions()` and the length of
+    /// `velocities` is `joint_type.dof()`. It is the same as the sum for the joints
+    /// which do not have a quaternion.
+    pub fn integrate_positions(&self, positions: &[T], velocities: &[T]) -> Vec<T> {
+        let add = |positions: &[T], velocities: &[T]| {
+            positions
+                .iter()
+                .zip(velocities.iter())
+                .map(|(position, velocity)| *position + *velocity)
+                .collect::<Vec<_>>()
+        };
+        let mut output = match self.joint_type.quaternion_offset() {
+            Some(offset) => {
+                let rotation = UnitQuaternion::from_scaled_axis(Vector3::new(
+                    velocities[offset],
+                    velocities[offset + 1],
+                    velocities[offset + 2],
+                )) * quaternion_from_slice(&positions[offset..offset + 4]);
+                let mut output = add(&positions[..offset], &velocities[..offset]);
+                output.extend_from_slice(rotation.coords.as_slice());
+                output
+            }
+            None => add(positions, velocities),
+        };
+        normalize_positions(&self.joint_type, &mut output);
+        output
+    }
+    /// Interpolate the positions from `from` to `to` by `ratio` (0.0 to 1.0)
+    ///
+    /// The rotations move along the shortest path.
+    pub fn interpolate_positions(&self, from: &[T], to: &[T], ratio: T) -> Vec<T> {
+        let velocities = self
+            .positions_difference(from, to)
+            .into_iter()
+            .map(|diff| diff * ratio)
+            .collect::<Vec<_>>();
+        self.integrate_positions(from, &velocities)
     }
     /// Returns the position (angle)
+    ///
+    /// It is `None` if the joint does not have exactly one position.
     #[inline]
     pub fn joint_position(&self) -> Option<T> {
-        match self.joint_type {
-            JointType::Fixed => None,
-            _ => Some(self.position),
+        match self.positions.as_slice() {
+            [position] => Some(*position),
+            _ => None,
         }
     }
+    /// Returns the positions, the length is `joint_type.num_positions()`
+    #[inline]
+    pub fn joint_positions(&self) -> &[T] {
+        &self.positions
+    }
 
     #[inline]
     pub fn origin(&self) -> &Isometry3<T> {
@@ -186,26 +645,178 @@ where
         self.world_transform_cache.replace(None);
     }
 
+    /// Set the velocity of the joint
+    ///
+    /// It returns Err if it is over the velocity limit, or this is fixed joint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nalgebra as na;
+    /// extern crate k;
+    ///
+    /// let mut rot = k::Joint::<f64>::new("r0", k::JointType::Rotational { axis: na::Vector3::y_axis() });
+    /// // Without limit, it always succeeds
+    /// rot.set_joint_velocity(3.0).unwrap();
+    /// rot.velocity_limit = Some(1.0);
+    /// assert!(rot.set_joint_velocity(2.0).is_err());
+    /// assert!(rot.set_joint_velocity(-0.5).is_ok());
+    /// assert_eq!(rot.joint_velocity().unwrap(), -0.5);
+    /// ```
     pub fn set_joint_velocity(&mut self, velocity: T) -> Result<(), Error> {
-        if let JointType::Fixed = self.joint_type {
+        self.set_joint_velocities(&[velocity])
+    }
+
+    /// Set the velocities of the joint
+    ///
+    /// The length of `velocities` must be `joint_type.dof()`.
+    /// All the elements are checked with the velocity limit.
+    pub fn set_joint_velocities(&mut self, velocities: &[T]) -> Result<(), Error> {
+        if !self.is_movable() {
             return Err(Error::SetToFixedError {
                 joint_name: self.name.to_string(),
             });
         }
-        self.velocity = velocity;
-        self.world_velocity_cache.replace(None);
+        if velocities.len() != self.velocities.len() {
+            return Err(Error::SizeMismatchError {
+                input: velocities.len(),
+                required: self.velocities.len(),
+            });
+        }
+        for velocity in velocities {
+            self.check_joint_velocity(*velocity)?;
+        }
+        self.set_joint_velocities_unchecked(velocities);
         Ok(())
     }
 
+    /// Set the velocity of the joint without checking the velocity limit
+    ///
+    /// It does nothing if this joint does not have exactly one degree of freedom.
+    pub fn set_joint_velocity_unchecked(&mut self, velocity: T) {
+        if self.velocities.len() == 1 {
+            self.set_joint_velocities_unchecked(&[velocity]);
+        }
+    }
+
+    /// Set the velocities of the joint without checking the velocity limit
+    pub fn set_joint_velocities_unchecked(&mut self, velocities: &[T]) {
+        self.velocities.copy_from_slice(velocities);
+        self.world_velocity_cache.replace(None);
+        self.world_acceleration_cache.replace(None);
+    }
+
     /// Returns the velocity
+    ///
+    /// It is `None` if the joint does not have exactly one degree of freedom.
     #[inline]
     pub fn joint_velocity(&self) -> Option<T> {
+        match self.velocities.as_slice() {
+            [velocity] => Some(*velocity),
+            _ => None,
+        }
+    }
+
+    /// Returns the velocities, the length is `joint_type.dof()`
+    #[inline]
+    pub fn joint_velocities(&self) -> &[T] {
+        &self.velocities
+    }
+
+    /// Set the acceleration of the joint
+    ///
+    /// It returns Err if this is fixed joint.
+    pub fn set_joint_acceleration(&mut self, acceleration: T) -> Result<(), Error> {
+        self.set_joint_accelerations(&[acceleration])
+    }
+
+    /// Set the accelerations of the joint
+    ///
+    /// The length of `accelerations` must be `joint_type.dof()`.
+    pub fn set_joint_accelerations(&mut self, accelerations: &[T]) -> Result<(), Error> {
+        if !self.is_movable() {
+            return Err(Error::SetToFixedError {
+                joint_name: self.name.to_string(),
+            });
+        }
+        if accelerations.len() != self.accelerations.len() {
+            return Err(Error::SizeMismatchError {
+                input: accelerations.len(),
+                required: self.accelerations.len(),
+            });
+        }
+        self.accelerations.copy_from_slice(accelerations);
+        self.world_acceleration_cache.replace(None);
+        Ok(())
+    }
+
+    /// Returns the acceleration
+    ///
+    /// It is `None` if the joint does not have exactly one degree of freedom.
+    #[inline]
+    pub fn joint_acceleration(&self) -> Option<T> {
+        match self.accelerations.as_slice() {
+            [acceleration] => Some(*acceleration),
+            _ => None,
+        }
+    }
+
+    /// Returns the accelerations, the length is `joint_type.dof()`
+    #[inline]
+    pub fn joint_accelerations(&self) -> &[T] {
+        &self.accelerations
+    }
+
+    /// Motion of the end of this joint for the unit velocity of the `index`-th degree of freedom
+    ///
+    /// The coordinates are the origin frame (the frame after `origin()`).
+    /// `linear` is the velocity of the end of this joint.
+    #[cfg(feature = "std")]
+    pub(crate) fn motion_subspace(&self, index: usize) -> Motion<T> {
+        motion_subspace_of(&self.joint_type, &self.positions, index)
+    }
+
+    /// Velocity of the end of this joint relative to the origin frame of this joint
+    ///
+    /// The coordinates are the same as `motion_subspace()`.
+    #[cfg(feature = "std")]
+    pub(crate) fn relative_velocity(&self) -> Motion<T> {
+        self.velocities
+            .iter()
+            .enumerate()
+            .fold(Motion::zero(), |sum, (i, velocity)| {
+                sum + self.motion_subspace(i) * *velocity
+            })
+    }
+
+    /// Acceleration of the end of this joint relative to the origin frame of this joint
+    ///
+    /// The coordinates are the same as `motion_subspace()`.
+    #[cfg(feature = "std")]
+    pub(crate) fn relative_acceleration(&self) -> Motion<T> {
+        let acceleration = self
+            .accelerations
+            .iter()
+            .enumerate()
+            .fold(Motion::zero(), |sum, (i, acceleration)| {
+                sum + self.motion_subspace(i) * *acceleration
+            });
         match self.joint_type {
-            JointType::Fixed => None,
-            _ => Some(self.velocity),
+            // the second axis moves with the velocity of the first angle
+            JointType::Universal { .. } => {
+                let w1 = self.motion_subspace(0).angular * self.velocities[0];
+                let w2 = self.motion_subspace(1).angular * self.velocities[1];
+                acceleration + Motion::from_parts(Vector3::zeros(), w1.cross(&w2))
+            }
+            _ => acceleration,
         }
     }
 
+    /// Transform by the positions of this joint, without the origin
+    pub(crate) fn joint_transform(&self) -> Isometry3<T> {
+        joint_transform_of(&self.joint_type, &self.positions)
+    }
+
     /// Calculate and returns the transform of the end of this joint
     ///
     /// # Examples
@@ -219,32 +830,50 @@ where
     /// assert_eq!(lin.local_transform().translation.vector.x, 0.0);
     /// lin.set_joint_position(-1.0).unwrap();
     /// assert_eq!(lin.local_transform().translation.vector.x, -1.0);
+    ///
+    /// // Planar joint on XY plane
+    /// let mut planar = k::Joint::<f64>::new("p0", k::JointType::Planar { normal: na::Vector3::z_axis() });
+    /// planar.set_joint_positions(&[1.0, 2.0, 0.5]).unwrap();
+    /// let trans = planar.local_transform();
+    /// assert!((trans.translation.vector - na::Vector3::new(1.0, 2.0, 0.0)).norm() < 1e-10);
+    /// assert!((trans.rotation.angle() - 0.5).abs() < 1e-10);
+    ///
+    /// // Helical joint which moves 0.01 per radian
+    /// let mut screw = k::Joint::<f64>::new("s0", k::JointType::Helical { axis: na::Vector3::z_axis(), pitch: 0.01 });
+    /// screw.set_joint_position(std::f64::consts::PI).unwrap();
+    /// let trans = screw.local_transform();
+    /// assert!((trans.translation.vector.z - 0.01 * std::f64::consts::PI).abs() < 1e-10);
+    /// assert!((trans.rotation.angle() - std::f64::consts::PI).abs() < 1e-10);
     /// ```
     ///
     pub fn local_transform(&self) -> Isometry3<T> {
-        let joint_transform = match self.joint_type {
-            JointType::Fixed => Isometry3::identity(),
-            JointType::Rotational { axis } => Isometry3::from_parts(
-                Translation3::new(T::zero(), T::zero(), T::zero()),
-                UnitQuaternion::from_axis_angle(&axis, self.position),
-            ),
-            JointType::Linear { axis } => Isometry3::from_parts(
-                Translation3::from(axis.into_inner() * self.position),
-                UnitQuaternion::identity(),
-            ),
-        };
-        self.origin * joint_transform
+        self.origin * self.joint_transform()
     }
 
     #[inline]
+    #[cfg(feature = "std")]
+    pub(crate) fn clear_world_transform(&self) {
+        self.world_transform_cache.replace(None);
+    }
+
+    #[inline]
+    #[cfg(feature = "std")]
     pub(crate) fn set_world_transform(&self, world_transform: Isometry3<T>) {
         self.world_transform_cache.replace(Some(world_transform));
     }
 
     #[inline]
+    #[cfg(feature = "std")]
     pub(crate) fn set_world_velocity(&self, world_velocity: Velocity<T>) {
         self.world_velocity_cache.replace(Some(world_velocity));
     }
+
+    #[inline]
+    #[cfg(feature = "std")]
+    pub(crate) fn set_world_acceleration(&self, world_acceleration: Motion<T>) {
+        self.world_acceleration_cache
+            .replace(Some(world_acceleration));
+    }
     /// Get the result of forward kinematics
     ///
     /// The value is updated by `Chain::update_transforms`
@@ -253,11 +882,23 @@ where
         *self.world_transform_cache.borrow()
     }
 
+    /// Get the velocity of the end of this joint in the world frame
+    ///
+    /// The value is updated by `Chain::update_velocities`
     #[inline]
     pub fn world_velocity(&self) -> Option<Velocity<T>> {
         *self.world_velocity_cache.borrow()
     }
 
+    /// Get the acceleration of the end of this joint in the world frame
+    ///
+    /// `angular` is the angular acceleration and `linear` is the linear acceleration
+    /// of the end of this joint. The value is updated by `Chain::update_accelerations`
+    #[inline]
+    pub fn world_acceleration(&self) -> Option<Motion<T>> {
+        *self.world_acceleration_cache.borrow()
+    }
+
     #[inline]
     pub fn is_movable(&self) -> bool {
         match self.joint_type {
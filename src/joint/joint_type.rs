@@ -13,10 +13,13 @@
   See the License for the specific language governing permissions and
   limitations under the License.
 */
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::fmt::{self, Display};
 use nalgebra::{RealField, Unit, Vector3};
-use std::fmt::{self, Display};
 
-/// Type of Joint, `Fixed`, `Rotational`, `Linear` is supported now
+/// Type of Joint, `Fixed`, `Rotational`, `Continuous`, `Linear`, `Helical`, `Planar`,
+/// `Floating`, `Spherical` and `Universal` are supported now
 #[derive(Copy, Debug, Clone)]
 pub enum JointType<T: RealField> {
     /// Fixed joint. It has no `joint_position` and axis.
@@ -26,11 +29,137 @@ pub enum JointType<T: RealField> {
         /// axis of the joint
         axis: Unit<Vector3<T>>,
     },
+    /// Rotational joint without limits. The position [rad] is wrapped to (-pi, pi].
+    Continuous {
+        /// axis of the joint
+        axis: Unit<Vector3<T>>,
+    },
     /// Linear joint. position is length
     Linear {
         /// axis of the joint
         axis: Unit<Vector3<T>>,
     },
+    /// Helical (screw) joint which rotates around and moves along the axis at the same time.
+    ///
+    /// The position is the angle [rad]. The translation is `pitch * angle`.
+    Helical {
+        /// axis of the joint
+        axis: Unit<Vector3<T>>,
+        /// translation per the angle [m/rad], i.e. the lead of the screw divided by 2 pi
+        pitch: T,
+    },
+    /// Planar joint which moves in the plane perpendicular to the normal.
+    ///
+    /// The positions are `[x, y, angle]`, the translation in the plane and the
+    /// rotation around the normal [rad].
+    Planar {
+        /// normal of the plane
+        normal: Unit<Vector3<T>>,
+    },
+    /// Floating joint which has 6 DOF.
+    ///
+    /// The positions are `[x, y, z, qx, qy, qz, qw]`, the translation and the quaternion.
+    /// The velocities are `[vx, vy, vz, wx, wy, wz]` in the origin frame of the joint.
+    Floating,
+    /// Spherical (ball) joint which rotates around any axis.
+    ///
+    /// The positions are the quaternion `[qx, qy, qz, qw]` and the velocities are
+    /// the angular velocity `[wx, wy, wz]` in the origin frame of the joint.
+    Spherical,
+    /// Universal joint which rotates around `axis1` and then `axis2`.
+    ///
+    /// The positions are the angles `[angle1, angle2]` [rad]. `axis2` is rotated by `angle1`.
+    Universal {
+        /// first axis of the joint
+        axis1: Unit<Vector3<T>>,
+        /// second axis of the joint
+        axis2: Unit<Vector3<T>>,
+    },
+}
+
+impl<T: RealField> JointType<T> {
+    /// Number of the position values (dimension of the configuration space)
+    ///
+    /// It is larger than `dof()` if the rotation is represented by a quaternion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// assert_eq!(JointType::<f64>::Fixed.num_positions(), 0);
+    /// assert_eq!(JointType::<f64>::Rotational{axis: Vector3::z_axis()}.num_positions(), 1);
+    /// assert_eq!(JointType::<f64>::Spherical.num_positions(), 4);
+    /// assert_eq!(JointType::<f64>::Floating.num_positions(), 7);
+    /// ```
+    pub fn num_positions(&self) -> usize {
+        match self {
+            JointType::Fixed => 0,
+            JointType::Rotational { .. }
+            | JointType::Continuous { .. }
+            | JointType::Linear { .. }
+            | JointType::Helical { .. } => 1,
+            JointType::Universal { .. } => 2,
+            JointType::Planar { .. } => 3,
+            JointType::Spherical => 4,
+            JointType::Floating => 7,
+        }
+    }
+    /// Degree of freedom (dimension of the velocity)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// assert_eq!(JointType::<f64>::Fixed.dof(), 0);
+    /// assert_eq!(JointType::<f64>::Planar{normal: Vector3::z_axis()}.dof(), 3);
+    /// assert_eq!(JointType::<f64>::Spherical.dof(), 3);
+    /// assert_eq!(JointType::<f64>::Floating.dof(), 6);
+    /// ```
+    pub fn dof(&self) -> usize {
+        match self {
+            JointType::Floating => 6,
+            JointType::Spherical => 3,
+            _ => self.num_positions(),
+        }
+    }
+    /// Positions of the joint at its origin
+    ///
+    /// They are zeros except for the `w` of the quaternions.
+    pub fn neutral_positions(&self) -> Vec<T> {
+        let mut positions = vec![T::zero(); self.num_positions()];
+        if let Some(offset) = self.quaternion_offset() {
+            positions[offset + 3] = T::one();
+        }
+        positions
+    }
+    /// Index of the quaternion `[qx, qy, qz, qw]` in the positions, if the joint has it
+    pub(crate) fn quaternion_offset(&self) -> Option<usize> {
+        match self {
+            JointType::Spherical => Some(0),
+            JointType::Floating => Some(3),
+            _ => None,
+        }
+    }
+}
+
+/// Wrap the angle to (-pi, pi]
+///
+/// # Examples
+///
+/// ```
+/// use std::f64::consts::PI;
+/// use k::joint::wrap_angle;
+///
+/// assert!((wrap_angle(0.5_f64) - 0.5).abs() < 1e-10);
+/// assert!((wrap_angle(PI) - PI).abs() < 1e-10);
+/// assert!((wrap_angle(-PI) - PI).abs() < 1e-10);
+/// assert!((wrap_angle(1.5 * PI) + 0.5 * PI).abs() < 1e-10);
+/// assert!((wrap_angle(-5.0 * PI) - PI).abs() < 1e-10);
+/// ```
+pub fn wrap_angle<T: RealField>(angle: T) -> T {
+    angle - T::two_pi() * ((angle - T::pi()) / T::two_pi()).ceil()
 }
 
 fn axis_to_string<T: RealField>(axis: &Unit<Vector3<T>>) -> &str {
@@ -56,7 +185,15 @@ impl<T: RealField> Display for JointType<T> {
         match self {
             JointType::Fixed => write!(f, "[⚓]"),
             JointType::Rotational { axis } => write!(f, "[⚙{}]", axis_to_string(axis)),
+            JointType::Continuous { axis } => write!(f, "[⟳{}]", axis_to_string(axis)),
             JointType::Linear { axis } => write!(f, "[↕{}]", axis_to_string(axis)),
+            JointType::Helical { axis, .. } => write!(f, "[⚙↕{}]", axis_to_string(axis)),
+            JointType::Planar { normal } => write!(f, "[▱{}]", axis_to_string(normal)),
+            JointType::Floating => write!(f, "[☁]"),
+            JointType::Spherical => write!(f, "[◎]"),
+            JointType::Universal { axis1, axis2 } => {
+                write!(f, "[⚙{}⚙{}]", axis_to_string(axis1), axis_to_string(axis2))
+            }
         }
     }
 }
@@ -13,8 +13,25 @@
   See the License for the specific language governing permissions and
   limitations under the License.
 */
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::fmt::{self, Debug};
+use nalgebra as na;
 use nalgebra::RealField;
 
+/// Relation which calculates the position of a joint from the positions of other joints
+///
+/// The joints which are the inputs are called "mimic parents" (see `Node::set_coupling`).
+/// `Mimic`, `LookupTable` and `FnCoupling` are available.
+pub trait Coupling<T: RealField>: Debug + Send + Sync {
+    /// Calculate the position from the positions of the mimic parents
+    fn position(&self, parent_positions: &[T]) -> T;
+    /// Partial derivatives of the position by the positions of the mimic parents
+    ///
+    /// They are used to calculate the velocities and the jacobian.
+    fn derivatives(&self, parent_positions: &[T]) -> Vec<T>;
+}
+
 /// Information for copying joint state of other joint
 ///
 /// For example, `Mimic` is used to calculate the position of the gripper(R) from
@@ -62,4 +79,176 @@ where
     pub fn mimic_position(&self, from_position: T) -> T {
         from_position * self.multiplier + self.origin
     }
+    /// Calculate the mimic joint velocity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let m = k::joint::Mimic::<f64>::new(-2.0, 0.5);
+    /// assert_eq!(m.mimic_velocity(0.2), -0.4); // 0.2 * -2.0
+    /// ```
+    pub fn mimic_velocity(&self, from_velocity: T) -> T {
+        from_velocity * self.multiplier
+    }
+}
+
+impl<T> Coupling<T> for Mimic<T>
+where
+    T: RealField,
+{
+    fn position(&self, parent_positions: &[T]) -> T {
+        self.mimic_position(parent_positions[0])
+    }
+    fn derivatives(&self, _parent_positions: &[T]) -> Vec<T> {
+        vec![self.multiplier]
+    }
+}
+
+/// Piecewise linear coupling from the position of one joint
+///
+/// The output is clamped by the first and the last points.
+///
+/// # Examples
+///
+/// ```
+/// use k::joint::{Coupling, LookupTable};
+///
+/// let table = LookupTable::new(vec![(0.0, 0.0), (1.0, 2.0), (2.0, 2.5)]);
+/// assert_eq!(table.position(&[0.5]), 1.0);
+/// assert_eq!(table.position(&[1.5]), 2.25);
+/// assert_eq!(table.position(&[3.0]), 2.5);
+/// assert_eq!(table.derivatives(&[0.5]), vec![2.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LookupTable<T: RealField> {
+    points: Vec<(T, T)>,
+}
+
+impl<T> LookupTable<T>
+where
+    T: RealField,
+{
+    /// Create the table from the points of `(parent position, position)`
+    ///
+    /// The points are sorted by the parent position. It panics if `points` is empty.
+    pub fn new(mut points: Vec<(T, T)>) -> Self {
+        assert!(!points.is_empty(), "LookupTable needs at least one point");
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+        LookupTable { points }
+    }
+    /// Returns the index of the segment which contains `x`, `None` if out of the table
+    fn segment(&self, x: T) -> Option<usize> {
+        self.points
+            .windows(2)
+            .position(|pair| pair[0].0 <= x && x <= pair[1].0 && pair[0].0 < pair[1].0)
+    }
+}
+
+impl<T> Coupling<T> for LookupTable<T>
+where
+    T: RealField,
+{
+    fn position(&self, parent_positions: &[T]) -> T {
+        let x = parent_positions[0];
+        match self.segment(x) {
+            Some(i) => {
+                let (x0, y0) = self.points[i];
+                let (x1, y1) = self.points[i + 1];
+                y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+            }
+            None if x < self.points[0].0 => self.points[0].1,
+            None => self.points[self.points.len() - 1].1,
+        }
+    }
+    fn derivatives(&self, parent_positions: &[T]) -> Vec<T> {
+        match self.segment(parent_positions[0]) {
+            Some(i) => {
+                let (x0, y0) = self.points[i];
+                let (x1, y1) = self.points[i + 1];
+                vec![(y1 - y0) / (x1 - x0)]
+            }
+            None => vec![T::zero()],
+        }
+    }
+}
+
+/// Coupling by a closure of the positions of the mimic parents
+///
+/// The derivatives are calculated numerically if they are not given.
+///
+/// # Examples
+///
+/// ```
+/// use k::joint::{Coupling, FnCoupling};
+///
+/// let coupling = FnCoupling::new(|q: &[f64]| q[0] * q[1]);
+/// assert_eq!(coupling.position(&[2.0, 3.0]), 6.0);
+/// let derivatives = coupling.derivatives(&[2.0, 3.0]);
+/// assert!((derivatives[0] - 3.0).abs() < 1e-6);
+/// assert!((derivatives[1] - 2.0).abs() < 1e-6);
+/// ```
+pub struct FnCoupling<T: RealField> {
+    position: Box<PositionFn<T>>,
+    derivatives: Option<Box<DerivativesFn<T>>>,
+}
+
+type PositionFn<T> = dyn Fn(&[T]) -> T + Send + Sync;
+type DerivativesFn<T> = dyn Fn(&[T]) -> Vec<T> + Send + Sync;
+
+impl<T> FnCoupling<T>
+where
+    T: RealField,
+{
+    /// Create the coupling from the function of the positions
+    pub fn new<F>(position: F) -> Self
+    where
+        F: Fn(&[T]) -> T + Send + Sync + 'static,
+    {
+        FnCoupling {
+            position: Box::new(position),
+            derivatives: None,
+        }
+    }
+    /// Set the function which calculates the partial derivatives
+    pub fn with_derivatives<F>(mut self, derivatives: F) -> Self
+    where
+        F: Fn(&[T]) -> Vec<T> + Send + Sync + 'static,
+    {
+        self.derivatives = Some(Box::new(derivatives));
+        self
+    }
+}
+
+impl<T: RealField> Debug for FnCoupling<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FnCoupling")
+    }
+}
+
+impl<T> Coupling<T> for FnCoupling<T>
+where
+    T: RealField,
+{
+    fn position(&self, parent_positions: &[T]) -> T {
+        (self.position)(parent_positions)
+    }
+    fn derivatives(&self, parent_positions: &[T]) -> Vec<T> {
+        if let Some(ref derivatives) = self.derivatives {
+            return derivatives(parent_positions);
+        }
+        // central difference
+        let step = na::convert(1e-6);
+        let mut positions = parent_positions.to_vec();
+        (0..positions.len())
+            .map(|i| {
+                let original = positions[i];
+                positions[i] = original + step;
+                let plus = (self.position)(&positions);
+                positions[i] = original - step;
+                let minus = (self.position)(&positions);
+                positions[i] = original;
+                (plus - minus) / (step + step)
+            })
+            .collect()
+    }
 }
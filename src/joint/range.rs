@@ -77,7 +77,7 @@ where
     }
 }
 
-impl<T> From<::std::ops::RangeInclusive<T>> for Range<T>
+impl<T> From<::core::ops::RangeInclusive<T>> for Range<T>
 where
     T: RealField,
 {
@@ -89,7 +89,7 @@ where
     /// assert!(range.is_valid(1.0));
     /// assert!(!range.is_valid(1.5));
     /// ```
-    fn from(range: ::std::ops::RangeInclusive<T>) -> Self {
+    fn from(range: ::core::ops::RangeInclusive<T>) -> Self {
         let (min, max) = range.into_inner();
         Range::new(min, max)
     }
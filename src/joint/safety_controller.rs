@@ -0,0 +1,76 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use super::range::*;
+use nalgebra::RealField;
+
+/// Soft limits and gains of the safety controller of URDF
+///
+/// The controller keeps the joint in `soft_limits`, which is inside the hard limits
+/// (`Joint::limits`). The hard limits are never exceeded.
+#[derive(Copy, Debug, Clone)]
+pub struct SafetyController<T: RealField> {
+    /// Soft lower and upper limits of the position
+    pub soft_limits: Range<T>,
+    /// Gain of the velocity bounds near the soft limits
+    pub k_position: T,
+    /// Gain of the effort bounds
+    pub k_velocity: T,
+}
+
+impl<T> SafetyController<T>
+where
+    T: RealField,
+{
+    /// Create new SafetyController instance
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let safety = k::joint::SafetyController::new((-1.0..=1.0).into(), 10.0, 5.0);
+    /// assert_eq!(safety.soft_limits.max, 1.0);
+    /// ```
+    pub fn new(soft_limits: Range<T>, k_position: T, k_velocity: T) -> Self {
+        Self {
+            soft_limits,
+            k_position,
+            k_velocity,
+        }
+    }
+
+    /// Bounds of the velocity at `position`
+    ///
+    /// The velocity towards the soft limit is reduced near the limit as ROS
+    /// safety controller: `-k_position * (position - soft_limit)`.
+    /// `velocity_limit` is applied if it exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let safety = k::joint::SafetyController::<f64>::new((-1.0..=1.0).into(), 10.0, 5.0);
+    /// let bounds = safety.velocity_bounds(0.9, Some(3.0));
+    /// assert!((bounds.max - 1.0).abs() < 1e-10);
+    /// assert_eq!(bounds.min, -3.0);
+    /// ```
+    pub fn velocity_bounds(&self, position: T, velocity_limit: Option<T>) -> Range<T> {
+        let mut min = -self.k_position * (position - self.soft_limits.min);
+        let mut max = -self.k_position * (position - self.soft_limits.max);
+        if let Some(limit) = velocity_limit {
+            min = min.max(-limit).min(limit);
+            max = max.min(limit).max(-limit);
+        }
+        Range::new(min, max)
+    }
+}
@@ -23,25 +23,90 @@
 //!
 //! See `Chain` as the top level interface.
 //!
+//! ## `no_std`
+//!
+//! Without the default `std` feature, `k` depends only on `core` and `alloc`.
+//! The joints, the transforms, `Model` (with `State`) and its jacobian are available,
+//! but `Node`, `Chain`, the IK solvers and the URDF loader are not.
+//! `Model` shares the couplings by `alloc::sync::Arc`, so the target must support
+//! atomic pointers (e.g. `thumbv7em-none-eabihf`, not `thumbv6m-none-eabi`).
+//! It builds with a stable compiler, e.g.
+//! `cargo build --no-default-features --target thumbv7em-none-eabihf`.
+//!
+//! ## Node backends
+//!
+//! `Node` is `Arc<Mutex<_>>` by default and all the accessors lock it. `Node`, `Chain`
+//! and the functions which use them are generic over `NodeBackend`, and `RcNode` and
+//! `RcChain` use `Rc<RefCell<_>>` with the same API, which is faster but not `Send`.
+//! `Chain::to_backend()` copies a chain into another backend, for example after loading URDF.
+//!
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod backend;
+mod batch;
+#[cfg(feature = "std")]
+mod centroidal;
+#[cfg(feature = "std")]
 mod chain;
+#[cfg(feature = "std")]
+mod closure;
 mod errors;
+#[cfg(feature = "std")]
+mod fixed_chain;
+#[cfg(feature = "std")]
 mod funcs;
+#[cfg(feature = "std")]
+mod handle;
+#[cfg(feature = "std")]
 mod ik;
+mod model;
+#[cfg(feature = "std")]
+mod transmission;
+#[cfg(feature = "std")]
+mod tree;
 use nalgebra as na;
+#[cfg(feature = "std")]
 pub mod iterator;
 pub mod joint;
 pub mod link;
+#[cfg(feature = "std")]
 pub mod node;
+#[cfg(feature = "std")]
 pub mod prelude;
+pub mod spatial;
+#[cfg(feature = "std")]
 pub mod urdf;
 
+#[cfg(feature = "std")]
+pub use self::backend::*;
+pub use self::batch::*;
+#[cfg(feature = "std")]
+pub use self::centroidal::*;
+#[cfg(feature = "std")]
 pub use self::chain::*;
+#[cfg(feature = "std")]
+pub use self::closure::*;
 pub use self::errors::*;
+#[cfg(feature = "std")]
+pub use self::fixed_chain::*;
+#[cfg(feature = "std")]
 pub use self::funcs::*;
+#[cfg(feature = "std")]
+pub use self::handle::*;
+#[cfg(feature = "std")]
 pub use self::ik::*;
 pub use self::joint::{Joint, JointType};
 pub use self::link::Link;
-pub use self::node::{Node, NodeBuilder};
+pub use self::model::*;
+#[cfg(feature = "std")]
+pub use self::node::{Node, NodeBuilder, RcNode};
+#[cfg(feature = "std")]
+pub use self::transmission::*;
+#[cfg(feature = "std")]
+pub use self::tree::*;
 
 // re-export from nalgebra
 // include Real for backwards compatibility purposes
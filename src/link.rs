@@ -17,6 +17,8 @@
 //!
 //! `link` module is optional for `k`.
 //!
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::ToOwned, string::String, string::ToString, vec::Vec};
 use na::{Isometry3, Matrix3, RealField, Vector3};
 use nalgebra as na;
 
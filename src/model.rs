@@ -0,0 +1,503 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+use na::{DMatrix, Isometry3, RealField, Vector6};
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+
+#[cfg(feature = "std")]
+use super::backend::*;
+#[cfg(feature = "std")]
+use super::chain::*;
+use super::errors::*;
+use super::joint::*;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::convert::TryFrom;
+
+/// Mimic joint in `Model`
+#[derive(Debug, Clone)]
+struct ModelCoupling<T: RealField> {
+    index: usize,
+    parents: Vec<usize>,
+    coupling: Arc<dyn Coupling<T>>,
+}
+
+/// Immutable kinematic description of a `Chain`
+///
+/// It is flat arrays of the parent indices, the joint types and the origins without
+/// any locks. The joint positions and the transforms are in `State`, so one `Model`
+/// can be shared by many threads which have their own `State`s.
+///
+/// The nodes are indexed in the order of `Chain::iter()`, the parents come before
+/// their children. The parent of the first node is the world.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+///
+/// let l0 = NodeBuilder::new()
+///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+///     .into_node();
+/// let l1 = NodeBuilder::new()
+///     .name("end")
+///     .translation(Translation3::new(1.0, 0.0, 0.0))
+///     .into_node();
+/// l1.set_parent(&l0);
+/// let model = Model::from_chain(&Chain::<f64>::from_root(l0)).unwrap();
+/// let mut state = model.create_state();
+/// model.set_positions(&mut state, &[std::f64::consts::FRAC_PI_2]).unwrap();
+/// model.forward_kinematics(&mut state);
+/// let end = state.transform(model.find("end").unwrap());
+/// assert!((end.translation.vector - Vector3::new(0.0, 1.0, 0.0)).norm() < 1e-10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Model<T: RealField> {
+    names: Vec<String>,
+    /// indices of the nodes by the joint names
+    name_indices: BTreeMap<String, usize>,
+    parents: Vec<Option<usize>>,
+    joint_types: Vec<JointType<T>>,
+    origins: Vec<Isometry3<T>>,
+    limits: Vec<Option<Range<T>>>,
+    /// offsets of the positions of the nodes in `State::positions()`
+    position_offsets: Vec<usize>,
+    /// offsets of the velocities of the nodes (columns of the jacobian)
+    velocity_offsets: Vec<usize>,
+    /// mimic joints in the order to be calculated
+    couplings: Vec<ModelCoupling<T>>,
+    num_positions: usize,
+    dof: usize,
+}
+
+/// Mutable state of a `Model`: the joint positions and the cached transforms
+#[derive(Debug, Clone)]
+pub struct State<T: RealField> {
+    positions: Vec<T>,
+    transforms: Vec<Isometry3<T>>,
+}
+
+impl<T: RealField> State<T> {
+    /// The positions of all the movable joints, the length is `Model::num_positions()`
+    pub fn positions(&self) -> &[T] {
+        &self.positions
+    }
+    /// The world transforms of the nodes calculated by `Model::forward_kinematics()`
+    pub fn transforms(&self) -> &[Isometry3<T>] {
+        &self.transforms
+    }
+    /// The world transform of the `index`-th node
+    pub fn transform(&self, index: usize) -> &Isometry3<T> {
+        &self.transforms[index]
+    }
+}
+
+impl<T> Model<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    /// Create the model from the joints and the indices of their parents
+    ///
+    /// The parent must come before the child. It is available without the `std`
+    /// feature, the mimic joints are not supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let model = Model::<f64>::from_joints(&[
+    ///     (Joint::new("j0", JointType::Rotational{axis: Vector3::z_axis()}), None),
+    ///     (Joint::new("j1", JointType::Linear{axis: Vector3::x_axis()}), Some(0)),
+    /// ]).unwrap();
+    /// assert_eq!(model.dof(), 2);
+    /// match Model::<f64>::from_joints(&[(Joint::new("j0", JointType::Fixed), Some(1))]) {
+    ///     Err(Error::InvalidParentError { index, parent }) => assert_eq!((index, parent), (0, 1)),
+    ///     _ => panic!("the parent must come before the child"),
+    /// }
+    /// ```
+    pub fn from_joints(joints: &[(Joint<T>, Option<usize>)]) -> Result<Self, Error> {
+        let mut model = Self::with_capacity(joints.len());
+        for (index, (joint, parent)) in joints.iter().enumerate() {
+            if let Some(parent) = parent {
+                if *parent >= index {
+                    return Err(Error::InvalidParentError {
+                        index,
+                        parent: *parent,
+                    });
+                }
+            }
+            model.push(joint, *parent);
+        }
+        Ok(model)
+    }
+
+    fn with_capacity(len: usize) -> Self {
+        Model {
+            names: Vec::with_capacity(len),
+            name_indices: BTreeMap::new(),
+            parents: Vec::with_capacity(len),
+            joint_types: Vec::with_capacity(len),
+            origins: Vec::with_capacity(len),
+            limits: Vec::with_capacity(len),
+            position_offsets: Vec::with_capacity(len),
+            velocity_offsets: Vec::with_capacity(len),
+            couplings: Vec::new(),
+            num_positions: 0,
+            dof: 0,
+        }
+    }
+
+    fn push(&mut self, joint: &Joint<T>, parent: Option<usize>) {
+        self.parents.push(parent);
+        // the first joint is found if the names are duplicated, as `Chain::find()`
+        self.name_indices
+            .entry(joint.name.to_owned())
+            .or_insert(self.names.len());
+        self.names.push(joint.name.to_owned());
+        self.joint_types.push(joint.joint_type);
+        self.origins.push(*joint.origin());
+        self.limits.push(joint.limits);
+        self.position_offsets.push(self.num_positions);
+        self.velocity_offsets.push(self.dof);
+        self.num_positions += joint.joint_type.num_positions();
+        self.dof += joint.joint_type.dof();
+    }
+
+    /// Create the model from the current structure of the chain
+    ///
+    /// The later changes of the chain are not reflected. It returns `Err` if the mimic
+    /// joints are coupled in a circle.
+    #[cfg(feature = "std")]
+    pub fn from_chain<B: NodeBackend<T>>(chain: &Chain<T, B>) -> Result<Self, Error> {
+        let mut index_of = HashMap::new();
+        let mut model = Self::with_capacity(chain.iter().count());
+        let mut unresolved = Vec::new();
+        for (index, node) in chain.iter().enumerate() {
+            let parent = node
+                .parent()
+                .and_then(|parent| index_of.get(&parent.as_ptr()).copied());
+            model.push(&node.joint(), parent);
+            index_of.insert(node.as_ptr(), index);
+        }
+        for (index, node) in chain.iter().enumerate() {
+            if let Some(coupling) = node.coupling() {
+                let parents = node
+                    .mimic_parents()
+                    .iter()
+                    .map(|parent| index_of.get(&parent.as_ptr()).copied())
+                    .collect::<Option<Vec<_>>>();
+                // the coupling to the nodes out of the chain is ignored
+                if let Some(parents) = parents {
+                    unresolved.push(ModelCoupling {
+                        index,
+                        parents,
+                        coupling,
+                    });
+                }
+            }
+        }
+        // sort the mimic joints to calculate the parents first
+        while !unresolved.is_empty() {
+            let is_ready = |coupling: &ModelCoupling<T>| {
+                coupling.parents.iter().all(|parent| {
+                    !unresolved
+                        .iter()
+                        .any(|unresolved| unresolved.index == *parent)
+                })
+            };
+            let (ready, rest): (Vec<_>, Vec<_>) =
+                unresolved.iter().cloned().partition(|c| is_ready(c));
+            if ready.is_empty() {
+                // all the rest have unresolved parents, so following them reaches a circle
+                let unresolved_parent = |index: usize| {
+                    rest.iter()
+                        .find(|coupling| coupling.index == index)
+                        .and_then(|coupling| {
+                            coupling
+                                .parents
+                                .iter()
+                                .find(|parent| rest.iter().any(|c| c.index == **parent))
+                        })
+                        .copied()
+                        .expect("unresolved coupling must have an unresolved parent")
+                };
+                let index =
+                    (0..rest.len()).fold(rest[0].index, |index, _| unresolved_parent(index));
+                return Err(Error::CircularCouplingError {
+                    joint_name: model.names[index].to_owned(),
+                });
+            }
+            model.couplings.extend(ready);
+            unresolved = rest;
+        }
+        Ok(model)
+    }
+
+    /// Number of the nodes
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    /// `true` if there are no nodes
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+    /// Degree of freedom (dimension of the velocities) including the mimic joints
+    pub fn dof(&self) -> usize {
+        self.dof
+    }
+    /// Number of the positions including the mimic joints
+    pub fn num_positions(&self) -> usize {
+        self.num_positions
+    }
+    /// Name of the `index`-th joint
+    pub fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+    /// Index of the parent of the `index`-th node, `None` for the root
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.parents[index]
+    }
+    /// Type of the `index`-th joint
+    pub fn joint_type(&self, index: usize) -> &JointType<T> {
+        &self.joint_types[index]
+    }
+    /// Origin of the `index`-th joint
+    pub fn origin(&self, index: usize) -> &Isometry3<T> {
+        &self.origins[index]
+    }
+    /// Find the index of the joint by name
+    pub fn find(&self, joint_name: &str) -> Option<usize> {
+        self.name_indices.get(joint_name).copied()
+    }
+    /// Offset of the positions of the `index`-th joint in `State::positions()`
+    pub fn position_offset(&self, index: usize) -> usize {
+        self.position_offsets[index]
+    }
+    /// Offset of the velocities of the `index`-th joint (the column in the jacobian)
+    pub fn velocity_offset(&self, index: usize) -> usize {
+        self.velocity_offsets[index]
+    }
+    /// The positions of the `index`-th joint in the state
+    pub fn joint_positions<'a>(&self, state: &'a State<T>, index: usize) -> &'a [T] {
+        let offset = self.position_offsets[index];
+        &state.positions[offset..offset + self.joint_types[index].num_positions()]
+    }
+
+    /// Create a new state with the neutral positions
+    pub fn create_state(&self) -> State<T> {
+        let mut positions = Vec::with_capacity(self.num_positions);
+        for joint_type in &self.joint_types {
+            positions.extend(joint_type.neutral_positions());
+        }
+        let mut state = State {
+            positions,
+            transforms: vec![Isometry3::identity(); self.len()],
+        };
+        self.update_coupled_positions(&mut state);
+        self.forward_kinematics(&mut state);
+        state
+    }
+
+    /// Set the positions of all the movable joints to the state
+    ///
+    /// It is the same as `Chain::set_joint_positions()`, the length must be
+    /// `num_positions()`, the limits are checked and the values of the mimic joints
+    /// are ignored. Call `forward_kinematics()` to update the transforms.
+    pub fn set_positions(&self, state: &mut State<T>, positions: &[T]) -> Result<(), Error> {
+        if positions.len() != self.num_positions {
+            return Err(Error::SizeMismatchError {
+                input: positions.len(),
+                required: self.num_positions,
+            });
+        }
+        for (index, joint_type) in self.joint_types.iter().enumerate() {
+            let offset = self.position_offsets[index];
+            let positions = &positions[offset..offset + joint_type.num_positions()];
+            if let (Some(range), [position]) = (&self.limits[index], positions) {
+                if !range.is_valid(*position) {
+                    return Err(Error::OutOfLimitError {
+                        joint_name: self.names[index].to_owned(),
+                        position: na::convert(*position),
+                        max_limit: na::convert(range.max),
+                        min_limit: na::convert(range.min),
+                    });
+                }
+            }
+        }
+        state.positions.copy_from_slice(positions);
+        for (index, joint_type) in self.joint_types.iter().enumerate() {
+            let offset = self.position_offsets[index];
+            normalize_positions(
+                joint_type,
+                &mut state.positions[offset..offset + joint_type.num_positions()],
+            );
+        }
+        self.update_coupled_positions(state);
+        Ok(())
+    }
+
+    fn update_coupled_positions(&self, state: &mut State<T>) {
+        self.apply_couplings(&mut state.positions, 1, 0);
+    }
+
+    /// `true` if there are mimic joints
+    pub(crate) fn has_couplings(&self) -> bool {
+        !self.couplings.is_empty()
+    }
+
+    /// Calculate the positions of the mimic joints of a sample
+    ///
+    /// The `i`-th position of the sample is `positions[i * stride + sample]`, so it can
+    /// be used for both `State` (`stride` is 1) and the batches.
+    pub(crate) fn apply_couplings(&self, positions: &mut [T], stride: usize, sample: usize) {
+        for coupled in &self.couplings {
+            if self.joint_types[coupled.index].num_positions() != 1 {
+                continue;
+            }
+            let parent_positions = coupled
+                .parents
+                .iter()
+                .map(|parent| {
+                    if self.joint_types[*parent].num_positions() == 0 {
+                        T::zero()
+                    } else {
+                        positions[self.position_offsets[*parent] * stride + sample]
+                    }
+                })
+                .collect::<Vec<_>>();
+            positions[self.position_offsets[coupled.index] * stride + sample] =
+                coupled.coupling.position(&parent_positions);
+        }
+    }
+
+    /// Calculate the world transforms of all the nodes of the state
+    pub fn forward_kinematics(&self, state: &mut State<T>) {
+        for index in 0..self.len() {
+            let local = self.origins[index]
+                * joint_transform_of(&self.joint_types[index], self.joint_positions(state, index));
+            state.transforms[index] = match self.parents[index] {
+                Some(parent) => state.transforms[parent] * local,
+                None => local,
+            };
+        }
+    }
+
+    /// Calculate the jacobian of the `end` node
+    ///
+    /// The columns are the velocities of all the joints (`dof()`), ordered as
+    /// `velocity_offset()`. The motions of the mimic joints are added to the columns
+    /// of the joints which drive them, so the columns of the mimic joints are zero.
+    /// Call `forward_kinematics()` before this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let model = Model::<f64>::from_joints(&[
+    ///     (Joint::new("j0", JointType::Rotational{axis: Vector3::z_axis()}), None),
+    ///     (Joint::new("end", JointType::Fixed), Some(0)),
+    /// ]).unwrap();
+    /// let mut state = model.create_state();
+    /// model.forward_kinematics(&mut state);
+    /// let jacobi = model.jacobian(&state, 1);
+    /// assert_eq!(jacobi.shape(), (6, 1));
+    /// assert_eq!(jacobi[(5, 0)], 1.0);
+    /// ```
+    pub fn jacobian(&self, state: &State<T>, end: usize) -> DMatrix<T> {
+        let mut jacobi = DMatrix::zeros(6, self.dof);
+        let p_n = state.transforms[end].translation.vector;
+        let mut current = Some(end);
+        while let Some(index) = current {
+            current = self.parents[index];
+            let joint_type = &self.joint_types[index];
+            let positions = self.joint_positions(state, index);
+            let t_i = &state.transforms[index];
+            // rotation of the origin frame of the joint
+            let rotation =
+                t_i.rotation * joint_transform_of(joint_type, positions).rotation.inverse();
+            for i in 0..joint_type.dof() {
+                let motion = motion_subspace_of(joint_type, positions, i);
+                let w_i = rotation * motion.angular;
+                let dp_i = rotation * motion.linear + w_i.cross(&(p_n - t_i.translation.vector));
+                let column = Vector6::new(dp_i[0], dp_i[1], dp_i[2], w_i[0], w_i[1], w_i[2]);
+                self.add_to_driving_columns(state, index, i, &column, T::one(), &mut jacobi);
+            }
+        }
+        jacobi
+    }
+
+    /// Add `column * weight` to the `i`-th column of the joint or the joints driving it
+    fn add_to_driving_columns(
+        &self,
+        state: &State<T>,
+        index: usize,
+        i: usize,
+        column: &Vector6<T>,
+        weight: T,
+        jacobi: &mut DMatrix<T>,
+    ) {
+        match self.couplings.iter().find(|coupled| coupled.index == index) {
+            Some(coupled) => {
+                let parent_positions = coupled
+                    .parents
+                    .iter()
+                    .map(|parent| {
+                        self.joint_positions(state, *parent)
+                            .first()
+                            .cloned()
+                            .unwrap_or_else(T::zero)
+                    })
+                    .collect::<Vec<_>>();
+                let derivatives = coupled.coupling.derivatives(&parent_positions);
+                for (parent, derivative) in coupled.parents.iter().zip(derivatives) {
+                    self.add_to_driving_columns(
+                        state,
+                        *parent,
+                        0,
+                        column,
+                        weight * derivative,
+                        jacobi,
+                    );
+                }
+            }
+            None => {
+                if i < self.joint_types[index].dof() {
+                    let mut target = jacobi.column_mut(self.velocity_offsets[index] + i);
+                    target += column * weight;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, B> TryFrom<&Chain<T, B>> for Model<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    type Error = Error;
+    fn try_from(chain: &Chain<T, B>) -> Result<Self, Error> {
+        Self::from_chain(chain)
+    }
+}
@@ -14,66 +14,107 @@
   limitations under the License.
 */
 //! graph structure for kinematic chain
-use na::{Isometry3, RealField, Translation3, UnitQuaternion};
+use na::{Isometry3, RealField, Translation3, UnitQuaternion, Vector3};
 use nalgebra as na;
 use simba::scalar::SubsetOf;
 use std::fmt::{self, Display};
 use std::ops::Deref;
-use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::sync::Arc;
 
+use super::backend::*;
 use super::errors::*;
 use super::iterator::*;
 use super::joint::*;
 use super::link::*;
+use super::spatial::{Motion, SpatialInertia};
 
-type WeakNode<T> = Weak<Mutex<NodeImpl<T>>>;
+/// The coupling and the mimic parents of a mimic joint
+type CouplingSources<T, B> = (Arc<dyn Coupling<T>>, Vec<Node<T, B>>);
+
+/// The mimic parents and the partial derivatives by their positions
+type CouplingDerivatives<T, B> = Vec<(Node<T, B>, T)>;
 
 #[derive(Debug)]
 /// Node for joint tree struct
-pub struct NodeImpl<T>
+pub struct NodeImpl<T, B = ArcMutexBackend>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    pub parent: Option<WeakNode<T>>,
-    pub children: Vec<Node<T>>,
+    pub parent: Option<B::Weak>,
+    pub children: Vec<Node<T, B>>,
     pub joint: Joint<T>,
-    pub mimic_parent: Option<WeakNode<T>>,
-    pub mimic_children: Vec<Node<T>>,
-    pub mimic: Option<Mimic<T>>,
+    pub mimic_parents: Vec<B::Weak>,
+    pub mimic_children: Vec<Node<T, B>>,
+    pub coupling: Option<Arc<dyn Coupling<T>>>,
     pub link: Option<Link<T>>,
+    pub payload: Option<Inertial<T>>,
 }
 
 /// Parts of `Chain`
 ///
 /// It contains joint, joint (transform), and parent/children.
-#[derive(Debug)]
-pub struct Node<T: RealField>(pub(crate) Arc<Mutex<NodeImpl<T>>>);
+///
+/// The node is `Arc<Mutex<NodeImpl>>` by default, and `Rc<RefCell<NodeImpl>>` (not `Send`)
+/// with `RcRefCellBackend` (`RcNode`).
+pub struct Node<T: RealField, B: NodeBackend<T> = ArcMutexBackend>(pub(crate) B::Shared);
+
+/// `Node` with `RcRefCellBackend`
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+///
+/// let l0: RcNode<f64> = NodeBuilder::new().finalize().into();
+/// let l1: RcNode<f64> = NodeBuilder::new().finalize().into();
+/// l1.set_parent(&l0);
+/// assert_eq!(RcChain::from_root(l0).iter().count(), 2);
+/// ```
+pub type RcNode<T> = Node<T, RcRefCellBackend>;
 
 impl<T> Node<T>
 where
     T: RealField + SubsetOf<f64>,
 {
-    pub(crate) fn from_rc(arc_mutex_node: Arc<Mutex<NodeImpl<T>>>) -> Self {
+    /// Create the node with the default backend, use `Node::from()` for the other backends
+    pub fn new(joint: Joint<T>) -> Self {
+        Self::from_joint(joint)
+    }
+}
+
+impl<T, B> Node<T, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    pub(crate) fn from_rc(arc_mutex_node: B::Shared) -> Self {
         Node(arc_mutex_node)
     }
 
-    pub fn new(joint: Joint<T>) -> Self {
-        Node::<T>(Arc::new(Mutex::new(NodeImpl {
+    pub(crate) fn from_joint(joint: Joint<T>) -> Self {
+        Node(B::new(NodeImpl {
             parent: None,
             children: Vec::new(),
             joint,
-            mimic_parent: None,
+            mimic_parents: Vec::new(),
             mimic_children: Vec::new(),
-            mimic: None,
+            coupling: None,
             link: None,
-        })))
+            payload: None,
+        }))
+    }
+
+    pub(crate) fn lock(&self) -> B::Guard<'_> {
+        B::lock(&self.0)
     }
 
-    pub(crate) fn lock(&self) -> MutexGuard<NodeImpl<T>> {
-        self.0.lock().unwrap()
+    /// The address of the shared node, which can be used as a key of maps
+    pub(crate) fn as_ptr(&self) -> *const () {
+        B::as_ptr(&self.0)
     }
 
-    pub fn joint(&self) -> JointRefGuard<T> {
+    pub fn joint(&self) -> JointRefGuard<T, B> {
         JointRefGuard { guard: self.lock() }
     }
 
@@ -81,40 +122,40 @@ where
         self.lock().joint.joint_position()
     }
 
-    pub fn parent(&self) -> Option<Node<T>> {
+    pub fn parent(&self) -> Option<Node<T, B>> {
         match self.lock().parent {
-            Some(ref weak) => weak
-                .upgrade()
-                .and_then(|rc| Some(Node::from_rc(rc.clone()))),
+            Some(ref weak) => B::upgrade(weak).map(Node::from_rc),
             None => None,
         }
     }
 
-    pub fn children(&self) -> ChildrenRefGuard<T> {
+    pub fn children(&self) -> ChildrenRefGuard<T, B> {
         ChildrenRefGuard { guard: self.lock() }
     }
 
     /// iter from the end to root, it contains nodes[id] itself
     #[inline]
-    pub fn iter_ancestors(&self) -> Ancestors<T> {
+    pub fn iter_ancestors(&self) -> Ancestors<T, B> {
         Ancestors::new(Some(self.clone()))
     }
     /// iter to the end, it contains nodes[id] itself
     #[inline]
-    pub fn iter_descendants(&self) -> Descendants<T> {
+    pub fn iter_descendants(&self) -> Descendants<T, B> {
         Descendants::new(vec![self.clone()])
     }
 
     /// Set parent and child relations at same time
-    pub fn set_parent(&self, parent: &Node<T>) {
-        self.lock().parent = Some(Arc::downgrade(&parent.0));
-        parent.0.lock().unwrap().children.push(self.clone());
+    pub fn set_parent(&self, parent: &Node<T, B>) {
+        self.lock().parent = Some(B::downgrade(&parent.0));
+        parent.lock().children.push(self.clone());
+        self.invalidate_world_transforms();
     }
 
     /// Remove parent and child relations at same time
-    pub fn remove_parent(&self, parent: &Node<T>) {
+    pub fn remove_parent(&self, parent: &Node<T, B>) {
         self.lock().parent = None;
-        parent.0.lock().unwrap().children.retain(|x| *x != *self);
+        parent.lock().children.retain(|x| *x != *self);
+        self.invalidate_world_transforms();
     }
 
     /// # Examples
@@ -142,13 +183,14 @@ where
     /// assert!(l1.is_end());
     /// ```
     pub fn is_end(&self) -> bool {
-        self.0.lock().unwrap().children.is_empty()
+        self.lock().children.is_empty()
     }
 
     /// Set the origin transform of the joint
     #[inline]
     pub fn set_origin(&self, trans: Isometry3<T>) {
         self.lock().joint.set_origin(trans);
+        self.invalidate_world_transforms();
     }
 
     /// Get the origin transform of the joint
@@ -203,29 +245,15 @@ where
     /// assert_eq!(j1.joint_position().unwrap(), 1.6);
     /// ```
     pub fn set_joint_position(&self, position: T) -> Result<(), Error> {
-        let mut node = self.lock();
-        if node.mimic_parent.is_some() {
-            return Ok(());
-        }
-        node.joint.set_joint_position(position)?;
-        for child in &node.mimic_children {
-            let mut child_node = child.lock();
-            let mimic = child_node.mimic.clone();
-            match mimic {
-                Some(m) => child_node
-                    .joint
-                    .set_joint_position(m.mimic_position(position))?,
-                None => {
-                    let from = self.joint().name.to_owned();
-                    let to = child.joint().name.to_owned();
-                    return Err(Error::MimicError {
-                        from: from.clone(),
-                        to: to.clone(),
-                    });
-                }
-            };
+        {
+            let mut node = self.lock();
+            if !node.mimic_parents.is_empty() {
+                return Ok(());
+            }
+            node.joint.set_joint_position(position)?;
         }
-        Ok(())
+        self.invalidate_world_transforms();
+        self.update_mimic_children(Node::update_coupled_position)
     }
 
     /// Set the clamped position (angle) of the joint
@@ -246,20 +274,282 @@ where
     /// assert_eq!(l0.joint().joint_position(), Some(-1.0));
     /// ```
     pub fn set_joint_position_clamped(&self, position: T) {
-        self.0
-            .lock()
-            .unwrap()
-            .joint
-            .set_joint_position_clamped(position);
+        {
+            let mut node = self.lock();
+            if !node.mimic_parents.is_empty() {
+                return;
+            }
+            node.joint.set_joint_position_clamped(position);
+        }
+        self.invalidate_world_transforms();
+        // clamping never fails
+        let _ = self.update_mimic_children(Node::update_coupled_position_clamped);
     }
 
     #[inline]
     pub fn set_joint_position_unchecked(&self, position: T) {
-        self.0
-            .lock()
-            .unwrap()
-            .joint
-            .set_joint_position_unchecked(position);
+        self.lock().joint.set_joint_position_unchecked(position);
+        self.invalidate_world_transforms();
+    }
+
+    /// Set the positions of the joint
+    ///
+    /// The length of `positions` must be `joint_type.num_positions()`.
+    /// It is the same as `set_joint_position` (including the mimic) for the joints
+    /// which have one position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Floating)
+    ///     .into_node();
+    /// assert!(l0.set_joint_positions(&[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]).is_ok());
+    /// assert!(l0.set_joint_positions(&[1.0]).is_err());
+    /// assert_eq!(l0.joint().joint_positions()[0], 1.0);
+    /// ```
+    pub fn set_joint_positions(&self, positions: &[T]) -> Result<(), Error> {
+        if positions.len() == 1 {
+            return self.set_joint_position(positions[0]);
+        }
+        self.lock().joint.set_joint_positions(positions)?;
+        self.invalidate_world_transforms();
+        Ok(())
+    }
+
+    /// Set the clamped positions of the joint
+    pub fn set_joint_positions_clamped(&self, positions: &[T]) {
+        if positions.len() == 1 {
+            self.set_joint_position_clamped(positions[0]);
+        } else {
+            self.lock().joint.set_joint_positions_clamped(positions);
+            self.invalidate_world_transforms();
+        }
+    }
+
+    #[inline]
+    pub fn set_joint_positions_unchecked(&self, positions: &[T]) {
+        self.lock().joint.set_joint_positions_unchecked(positions);
+        self.invalidate_world_transforms();
+    }
+
+    /// Set the velocity of the joint
+    ///
+    /// If the velocity is over the velocity limit, it returns Err.
+    /// The velocities of the mimic children are also updated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Linear{axis: Vector3::z_axis()})
+    ///     .velocity_limit(Some(1.0))
+    ///     .into_node();
+    /// assert!(l0.set_joint_velocity(0.5).is_ok());
+    /// assert!(l0.set_joint_velocity(-2.0).is_err());
+    /// assert_eq!(l0.joint().joint_velocity(), Some(0.5));
+    /// ```
+    pub fn set_joint_velocity(&self, velocity: T) -> Result<(), Error> {
+        {
+            let mut node = self.lock();
+            if !node.mimic_parents.is_empty() {
+                return Ok(());
+            }
+            node.joint.set_joint_velocity(velocity)?;
+        }
+        self.update_mimic_children(Node::update_coupled_velocity)
+    }
+
+    /// Set the acceleration of the joint
+    ///
+    /// The accelerations of the mimic children are also updated.
+    /// The second order terms of the nonlinear couplings are ignored.
+    pub fn set_joint_acceleration(&self, acceleration: T) -> Result<(), Error> {
+        {
+            let mut node = self.lock();
+            if !node.mimic_parents.is_empty() {
+                return Ok(());
+            }
+            node.joint.set_joint_acceleration(acceleration)?;
+        }
+        self.update_mimic_children(Node::update_coupled_acceleration)
+    }
+
+    fn update_mimic_children(
+        &self,
+        update: fn(&Node<T, B>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let children = self.lock().mimic_children.clone();
+        for child in &children {
+            update(child)?;
+        }
+        Ok(())
+    }
+
+    /// The coupling and the mimic parents, `None` if this is not a mimic joint
+    fn coupling_sources(&self) -> Result<Option<CouplingSources<T, B>>, Error> {
+        let node = self.lock();
+        let coupling = match node.coupling {
+            Some(ref coupling) => coupling.clone(),
+            None => return Ok(None),
+        };
+        let parents = node
+            .mimic_parents
+            .iter()
+            .map(|weak| {
+                B::upgrade(weak)
+                    .map(Node::from_rc)
+                    .ok_or_else(|| Error::MimicError {
+                        from: "(dropped node)".to_owned(),
+                        to: node.joint.name.to_owned(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some((coupling, parents)))
+    }
+
+    /// Partial derivatives of the position of this joint by the positions of the mimic parents
+    ///
+    /// It returns `Ok(None)` if this is not a mimic joint, and `Err` if a mimic parent
+    /// has been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::joint::Mimic;
+    ///
+    /// let child = NodeBuilder::<f64>::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// assert!(child.coupling_derivatives().unwrap().is_none());
+    /// {
+    ///     let parent = NodeBuilder::new()
+    ///         .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///         .into_node();
+    ///     child.set_mimic_parent(&parent, Mimic::new(2.0, 0.0));
+    ///     let derivatives = child.coupling_derivatives().unwrap().unwrap();
+    ///     assert_eq!(derivatives[0].1, 2.0);
+    /// }
+    /// // the parent has been dropped
+    /// assert!(child.coupling_derivatives().is_err());
+    /// ```
+    pub fn coupling_derivatives(&self) -> Result<Option<CouplingDerivatives<T, B>>, Error> {
+        let (coupling, parents) = match self.coupling_sources()? {
+            Some(sources) => sources,
+            None => return Ok(None),
+        };
+        let positions = Self::parent_values(&parents, Joint::joint_position);
+        Ok(Some(
+            parents
+                .into_iter()
+                .zip(coupling.derivatives(&positions))
+                .collect(),
+        ))
+    }
+
+    fn parent_values(parents: &[Node<T, B>], value: fn(&Joint<T>) -> Option<T>) -> Vec<T> {
+        parents
+            .iter()
+            .map(|parent| value(&parent.joint()).unwrap_or_else(T::zero))
+            .collect()
+    }
+
+    /// Calculate the value of this joint from the values of the mimic parents by the derivatives
+    fn coupled_value(&self, value: fn(&Joint<T>) -> Option<T>) -> Result<Option<T>, Error> {
+        let (coupling, parents) = match self.coupling_sources()? {
+            Some(sources) => sources,
+            None => return Ok(None),
+        };
+        let positions = Self::parent_values(&parents, Joint::joint_position);
+        let values = Self::parent_values(&parents, value);
+        Ok(Some(
+            coupling
+                .derivatives(&positions)
+                .into_iter()
+                .zip(values)
+                .fold(T::zero(), |sum, (derivative, value)| {
+                    sum + derivative * value
+                }),
+        ))
+    }
+
+    fn update_coupled_position(&self) -> Result<(), Error> {
+        if let Some((coupling, parents)) = self.coupling_sources()? {
+            let positions = Self::parent_values(&parents, Joint::joint_position);
+            self.lock()
+                .joint
+                .set_joint_position(coupling.position(&positions))?;
+            self.invalidate_world_transforms();
+        }
+        self.update_mimic_children(Node::update_coupled_position)
+    }
+
+    fn update_coupled_position_clamped(&self) -> Result<(), Error> {
+        if let Some((coupling, parents)) = self.coupling_sources()? {
+            let positions = Self::parent_values(&parents, Joint::joint_position);
+            self.lock()
+                .joint
+                .set_joint_position_clamped(coupling.position(&positions));
+            self.invalidate_world_transforms();
+        }
+        self.update_mimic_children(Node::update_coupled_position_clamped)
+    }
+
+    fn update_coupled_velocity(&self) -> Result<(), Error> {
+        if let Some(velocity) = self.coupled_value(Joint::joint_velocity)? {
+            self.lock().joint.set_joint_velocity(velocity)?;
+        }
+        self.update_mimic_children(Node::update_coupled_velocity)
+    }
+
+    fn update_coupled_acceleration(&self) -> Result<(), Error> {
+        if let Some(acceleration) = self.coupled_value(Joint::joint_acceleration)? {
+            self.lock().joint.set_joint_acceleration(acceleration)?;
+        }
+        self.update_mimic_children(Node::update_coupled_acceleration)
+    }
+
+    /// Set the velocities of the joint
+    ///
+    /// The length of `velocities` must be `joint_type.dof()`.
+    /// It is the same as `set_joint_velocity` for the joints which have one degree of freedom.
+    pub fn set_joint_velocities(&self, velocities: &[T]) -> Result<(), Error> {
+        if velocities.len() == 1 {
+            return self.set_joint_velocity(velocities[0]);
+        }
+        self.lock().joint.set_joint_velocities(velocities)
+    }
+
+    /// Set the accelerations of the joint
+    ///
+    /// The length of `accelerations` must be `joint_type.dof()`.
+    /// It is the same as `set_joint_acceleration` for the joints which have one degree of freedom.
+    pub fn set_joint_accelerations(&self, accelerations: &[T]) -> Result<(), Error> {
+        if accelerations.len() == 1 {
+            return self.set_joint_acceleration(accelerations[0]);
+        }
+        self.lock().joint.set_joint_accelerations(accelerations)
+    }
+
+    /// Clear the world transform caches of this node and its descendants
+    ///
+    /// `Chain::update_transforms()` recalculates only the nodes without the cache.
+    /// The descendants of a node without the cache never have the cache,
+    /// so the nodes which are already cleared are skipped.
+    pub(crate) fn invalidate_world_transforms(&self) {
+        let children = {
+            let node = self.lock();
+            node.joint.clear_world_transform();
+            node.children.clone()
+        };
+        for child in children {
+            if child.world_transform().is_some() {
+                child.invalidate_world_transforms();
+            }
+        }
     }
 
     pub(crate) fn parent_world_transform(&self) -> Option<Isometry3<T>> {
@@ -277,6 +567,13 @@ where
         }
     }
 
+    pub(crate) fn parent_world_acceleration(&self) -> Option<Motion<T>> {
+        match self.parent() {
+            Some(ref parent) => parent.world_acceleration(),
+            None => Some(Motion::zero()),
+        }
+    }
+
     /// Get the calculated world transform.
     /// Call `Chain::update_transforms()` before using this method.
     ///
@@ -309,54 +606,277 @@ where
     pub fn world_transform(&self) -> Option<Isometry3<T>> {
         self.joint().world_transform()
     }
+    /// Get the calculated world velocity.
+    /// Call `Chain::update_velocities()` before using this method.
     #[inline]
     pub fn world_velocity(&self) -> Option<Velocity<T>> {
         self.joint().world_velocity()
     }
+    /// Get the calculated world acceleration.
+    /// Call `Chain::update_accelerations()` before using this method.
+    #[inline]
+    pub fn world_acceleration(&self) -> Option<Motion<T>> {
+        self.joint().world_acceleration()
+    }
+    /// Get the velocity (twist) of this node in its own frame.
+    /// Call `Chain::update_velocities()` before using this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let l0 = NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .translation(Translation3::new(1.0, 0.0, 0.0))
+    ///     .rotation(UnitQuaternion::from_euler_angles(0.0, 0.0, 1.57))
+    ///     .into_node();
+    /// l1.set_parent(&l0);
+    /// let chain = Chain::<f64>::from_root(l0);
+    /// chain.set_joint_velocities(&[1.0]).unwrap();
+    /// chain.update_velocities();
+    /// // l1 moves to +y of the world, it is +x of l1's frame.
+    /// let v = l1.body_velocity().unwrap();
+    /// assert!((v.linear.x - 1.0).abs() < 0.001);
+    /// assert!((v.angular.z - 1.0).abs() < 0.001);
+    /// ```
+    pub fn body_velocity(&self) -> Option<Motion<T>> {
+        let joint = self.joint();
+        let trans = joint.world_transform()?;
+        let velocity = joint.world_velocity()?;
+        Some(Motion::from_parts(
+            trans.rotation.inverse() * velocity.translation,
+            trans.rotation.inverse() * velocity.rotation,
+        ))
+    }
+    /// Get the world velocity of the `point` which is fixed to this node.
+    ///
+    /// `point` is in the frame of this node, for example, the tool center point
+    /// of the end effector. Call `Chain::update_velocities()` before using this method.
+    pub fn point_velocity(&self, point: &Vector3<T>) -> Option<Velocity<T>> {
+        let joint = self.joint();
+        let trans = joint.world_transform()?;
+        let velocity = joint.world_velocity()?;
+        Some(Velocity::from_parts(
+            velocity.translation + velocity.rotation.cross(&(trans.rotation * point)),
+            velocity.rotation,
+        ))
+    }
 
-    pub fn mimic_parent(&self) -> Option<Node<T>> {
-        match self.lock().mimic_parent {
-            Some(ref weak) => weak
-                .upgrade()
-                .and_then(|rc| Some(Node::from_rc(rc.clone()))),
-            None => None,
+    /// True if the position of this joint is calculated from other joints
+    pub fn is_mimic(&self) -> bool {
+        !self.lock().mimic_parents.is_empty()
+    }
+
+    /// The first mimic parent
+    pub fn mimic_parent(&self) -> Option<Node<T, B>> {
+        self.mimic_parents().into_iter().next()
+    }
+
+    /// The joints which are the inputs of the coupling of this joint
+    pub fn mimic_parents(&self) -> Vec<Node<T, B>> {
+        self.lock()
+            .mimic_parents
+            .iter()
+            .filter_map(|weak| B::upgrade(weak).map(Node::from_rc))
+            .collect()
+    }
+
+    /// The joints whose positions are calculated from this joint
+    pub(crate) fn mimic_children(&self) -> Vec<Node<T, B>> {
+        self.lock().mimic_children.clone()
+    }
+
+    /// The coupling to calculate the position of this joint
+    pub fn coupling(&self) -> Option<Arc<dyn Coupling<T>>> {
+        self.lock().coupling.clone()
+    }
+
+    pub fn set_mimic_parent(&self, parent: &Node<T, B>, mimic: Mimic<T>) {
+        self.set_coupling(&[parent], mimic);
+    }
+
+    /// Make this joint follow the positions of `parents` by `coupling`
+    ///
+    /// The joints must have one position. The positions of this joint can not be set directly
+    /// after this, they are updated when the positions of the parents are set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::joint::{FnCoupling, LookupTable};
+    ///
+    /// let new_node = || NodeBuilder::new()
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let (j0, j1, j2, j3) = (new_node(), new_node(), new_node(), new_node());
+    /// // nonlinear mapping by a table
+    /// j2.set_coupling(&[&j0], LookupTable::new(vec![(0.0, 0.0), (1.0, 0.5), (2.0, 2.0)]));
+    /// // the sum of two joints
+    /// j3.set_coupling(&[&j0, &j1], FnCoupling::new(|q: &[f64]| q[0] + q[1]));
+    /// j0.set_joint_position(1.5).unwrap();
+    /// j1.set_joint_position(0.2).unwrap();
+    /// assert_eq!(j2.joint_position().unwrap(), 1.25);
+    /// assert!((j3.joint_position().unwrap() - 1.7).abs() < 1e-10);
+    /// ```
+    pub fn set_coupling<C>(&self, parents: &[&Node<T, B>], coupling: C)
+    where
+        C: Coupling<T> + 'static,
+    {
+        self.set_coupling_arc(parents, Arc::new(coupling));
+    }
+
+    pub(crate) fn set_coupling_arc(&self, parents: &[&Node<T, B>], coupling: Arc<dyn Coupling<T>>) {
+        for parent in self.mimic_parents() {
+            parent.lock().mimic_children.retain(|child| child != self);
         }
+        for parent in parents {
+            parent.lock().mimic_children.push(self.clone());
+        }
+        let mut node = self.lock();
+        node.mimic_parents = parents
+            .iter()
+            .map(|parent| B::downgrade(&parent.0))
+            .collect();
+        node.coupling = Some(coupling);
     }
 
-    pub fn set_mimic_parent(&self, parent: &Node<T>, mimic: Mimic<T>) {
-        self.lock().mimic_parent = Some(Arc::downgrade(&parent.0));
-        parent.lock().mimic_children.push(self.clone());
-        self.lock().mimic = Some(mimic);
+    /// Remove the coupling, then this joint is not a mimic joint anymore
+    pub(crate) fn clear_coupling(&self) {
+        for parent in self.mimic_parents() {
+            parent.lock().mimic_children.retain(|child| child != self);
+        }
+        let mut node = self.lock();
+        node.mimic_parents.clear();
+        node.coupling = None;
     }
 
     pub fn set_link(&self, link: Option<Link<T>>) {
         self.lock().link = link;
     }
 
-    pub fn link(&self) -> OptionLinkRefGuard<T> {
+    pub fn link(&self) -> OptionLinkRefGuard<T, B> {
         OptionLinkRefGuard { guard: self.lock() }
     }
+
+    /// Attach (or detach by `None`) an additional mass to this node
+    ///
+    /// For example, the object which is grasped by the gripper.
+    /// The origin of the `Inertial` is the offset from this node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::link::*;
+    ///
+    /// let hand = NodeBuilder::<f64>::new().into_node();
+    /// hand.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(1.0)).finalize()));
+    /// assert_eq!(hand.inertia().unwrap().mass, 1.0);
+    /// hand.set_payload(Some(Inertial::from_mass(0.5)));
+    /// assert_eq!(hand.inertia().unwrap().mass, 1.5);
+    /// hand.set_payload(None);
+    /// assert_eq!(hand.inertia().unwrap().mass, 1.0);
+    /// ```
+    pub fn set_payload(&self, payload: Option<Inertial<T>>) {
+        self.lock().payload = payload;
+    }
+
+    pub fn payload(&self) -> Option<Inertial<T>> {
+        self.lock().payload.clone()
+    }
+
+    /// Inertia of the link and the payload in the frame of this node
+    ///
+    /// It returns `None` if this node has neither link nor payload.
+    pub fn inertia(&self) -> Option<SpatialInertia<T>> {
+        let node = self.lock();
+        let link = node
+            .link
+            .as_ref()
+            .map(|link| SpatialInertia::from(&link.inertial));
+        let payload = node.payload.as_ref().map(SpatialInertia::from);
+        match (link, payload) {
+            (Some(link), Some(payload)) => Some(link + payload),
+            (link, payload) => link.or(payload),
+        }
+    }
+
+    /// Composite inertia of this node and all the descendants in the frame of this node
+    ///
+    /// It depends on the current joint positions of the descendants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::link::*;
+    ///
+    /// let l0 = NodeBuilder::<f64>::new().into_node();
+    /// let l1 = NodeBuilder::new()
+    ///     .translation(Translation3::new(0.0, 0.0, 1.0))
+    ///     .joint_type(JointType::Linear{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// l0.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(1.0)).finalize()));
+    /// l1.set_link(Some(LinkBuilder::new().inertial(Inertial::from_mass(3.0)).finalize()));
+    /// l1.set_parent(&l0);
+    /// l1.set_joint_position(1.0).unwrap();
+    /// let inertia = l0.composite_inertia();
+    /// assert_eq!(inertia.mass, 4.0);
+    /// assert!((inertia.com.z - 1.5).abs() < 1e-10);
+    /// ```
+    pub fn composite_inertia(&self) -> SpatialInertia<T> {
+        let mut inertia = self.inertia().unwrap_or_else(SpatialInertia::zero);
+        let children = self.children().clone();
+        for child in children {
+            let local_transform = child.joint().local_transform();
+            inertia += child.composite_inertia().transform(&local_transform);
+        }
+        inertia
+    }
+
+    /// Total mass of this node and all the descendants
+    pub fn subtree_mass(&self) -> T {
+        self.iter_descendants()
+            .filter_map(|node| node.inertia())
+            .fold(T::zero(), |mass, inertia| mass + inertia.mass)
+    }
 }
 
-impl<T> ::std::clone::Clone for Node<T>
+impl<T, B> ::std::clone::Clone for Node<T, B>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
     fn clone(&self) -> Self {
-        Node::<T>(self.0.clone())
+        Node(self.0.clone())
+    }
+}
+
+impl<T, B> PartialEq for Node<T, B>
+where
+    T: RealField,
+    B: NodeBackend<T>,
+{
+    fn eq(&self, other: &Node<T, B>) -> bool {
+        B::as_ptr(&self.0) == B::as_ptr(&other.0)
     }
 }
 
-impl<T> PartialEq for Node<T>
+impl<T, B> fmt::Debug for Node<T, B>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    fn eq(&self, other: &Node<T>) -> bool {
-        &*self.0 as *const Mutex<NodeImpl<T>> == &*other.0 as *const Mutex<NodeImpl<T>>
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Node").field(&self.0).finish()
     }
 }
 
-impl<T: RealField + SubsetOf<f64>> Display for Node<T> {
+impl<T: RealField + SubsetOf<f64>, B: NodeBackend<T>> Display for Node<T, B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let inner = self.lock();
         inner.joint.fmt(f)?;
@@ -368,27 +888,30 @@ impl<T: RealField + SubsetOf<f64>> Display for Node<T> {
     }
 }
 
-impl<T> From<Joint<T>> for Node<T>
+impl<T, B> From<Joint<T>> for Node<T, B>
 where
     T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
 {
     fn from(joint: Joint<T>) -> Self {
-        Self::new(joint)
+        Self::from_joint(joint)
     }
 }
 
 macro_rules! def_ref_guard {
     ($guard_struct:ident, $target:ty, $member:ident) => {
-        pub struct $guard_struct<'a, T>
+        pub struct $guard_struct<'a, T, B = ArcMutexBackend>
         where
             T: RealField,
+            B: NodeBackend<T>,
         {
-            guard: MutexGuard<'a, NodeImpl<T>>,
+            guard: B::Guard<'a>,
         }
 
-        impl<'a, T> Deref for $guard_struct<'a, T>
+        impl<'a, T, B> Deref for $guard_struct<'a, T, B>
         where
             T: RealField,
+            B: NodeBackend<T>,
         {
             type Target = $target;
             fn deref(&self) -> &Self::Target {
@@ -434,18 +957,20 @@ macro_rules! def_ref_guard_mut {
 def_ref_guard!(JointRefGuard, Joint<T>, joint);
 def_ref_guard!(OptionLinkRefGuard, Option<Link<T>>, link);
 //def_ref_guard!(LinkRefGuard, Link<T>, link);
-def_ref_guard!(ChildrenRefGuard, Vec<Node<T>>, children);
+def_ref_guard!(ChildrenRefGuard, Vec<Node<T, B>>, children);
 
-pub struct LinkRefGuard<'a, T>
+pub struct LinkRefGuard<'a, T, B = ArcMutexBackend>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
-    pub(crate) guard: MutexGuard<'a, NodeImpl<T>>,
+    pub(crate) guard: B::Guard<'a>,
 }
 
-impl<'a, T> Deref for LinkRefGuard<'a, T>
+impl<'a, T, B> Deref for LinkRefGuard<'a, T, B>
 where
     T: RealField,
+    B: NodeBackend<T>,
 {
     type Target = Link<T>;
     fn deref(&self) -> &Self::Target {
@@ -507,6 +1032,9 @@ pub struct NodeBuilder<T: RealField> {
     name: String,
     joint_type: JointType<T>,
     limits: Option<Range<T>>,
+    safety_controller: Option<SafetyController<T>>,
+    velocity_limit: Option<T>,
+    effort_limit: Option<T>,
     origin: Isometry3<T>,
 }
 
@@ -528,6 +1056,9 @@ where
             name: "".to_string(),
             joint_type: JointType::Fixed,
             limits: None,
+            safety_controller: None,
+            velocity_limit: None,
+            effort_limit: None,
             origin: Isometry3::identity(),
         }
     }
@@ -546,6 +1077,24 @@ where
         self.limits = limits;
         self
     }
+    /// Set the soft limits
+    pub fn safety_controller(
+        mut self,
+        safety_controller: Option<SafetyController<T>>,
+    ) -> NodeBuilder<T> {
+        self.safety_controller = safety_controller;
+        self
+    }
+    /// Set the limit of the absolute velocity
+    pub fn velocity_limit(mut self, velocity_limit: Option<T>) -> NodeBuilder<T> {
+        self.velocity_limit = velocity_limit;
+        self
+    }
+    /// Set the limit of the absolute effort
+    pub fn effort_limit(mut self, effort_limit: Option<T>) -> NodeBuilder<T> {
+        self.effort_limit = effort_limit;
+        self
+    }
     /// Set the origin transform of this joint
     pub fn origin(mut self, origin: Isometry3<T>) -> NodeBuilder<T> {
         self.origin = origin;
@@ -566,6 +1115,9 @@ where
         let mut joint = Joint::new(&self.name, self.joint_type);
         joint.set_origin(self.origin);
         joint.limits = self.limits;
+        joint.safety_controller = self.safety_controller;
+        joint.velocity_limit = self.velocity_limit;
+        joint.effort_limit = self.effort_limit;
         joint
     }
     /// Create `Node` instead of `Joint` as output
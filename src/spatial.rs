@@ -0,0 +1,452 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+//! Spatial (6D) vectors and inertia for velocity and dynamics calculations
+//!
+//! `Motion` is a twist (angular velocity and linear velocity of the origin of the frame),
+//! `Force` is a wrench (moment around the origin of the frame and force).
+//! As the same as `jacobian`, `to_vector` returns `[linear, angular]`.
+use core::ops::{Add, AddAssign, Mul, Neg, Sub};
+use na::{Isometry3, Matrix3, Matrix6, Point3, RealField, Vector3, Vector6};
+use nalgebra as na;
+
+use super::joint::Velocity;
+use super::link::Inertial;
+
+/// Spatial motion vector (twist)
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct Motion<T: RealField> {
+    /// angular velocity
+    pub angular: Vector3<T>,
+    /// linear velocity of the origin of the frame
+    pub linear: Vector3<T>,
+}
+
+/// Spatial force vector (wrench)
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct Force<T: RealField> {
+    /// moment around the origin of the frame
+    pub angular: Vector3<T>,
+    /// force
+    pub linear: Vector3<T>,
+}
+
+macro_rules! def_spatial_vector {
+    ($name:ident) => {
+        impl<T> $name<T>
+        where
+            T: RealField,
+        {
+            /// Create from linear and angular parts
+            pub fn from_parts(linear: Vector3<T>, angular: Vector3<T>) -> Self {
+                Self { angular, linear }
+            }
+            pub fn zero() -> Self {
+                Self {
+                    angular: Vector3::zeros(),
+                    linear: Vector3::zeros(),
+                }
+            }
+            /// Create from `[linear, angular]` vector
+            pub fn from_vector(vec: &Vector6<T>) -> Self {
+                Self {
+                    linear: Vector3::new(vec[0], vec[1], vec[2]),
+                    angular: Vector3::new(vec[3], vec[4], vec[5]),
+                }
+            }
+            /// Returns `[linear, angular]` vector
+            pub fn to_vector(&self) -> Vector6<T> {
+                Vector6::new(
+                    self.linear[0],
+                    self.linear[1],
+                    self.linear[2],
+                    self.angular[0],
+                    self.angular[1],
+                    self.angular[2],
+                )
+            }
+        }
+
+        impl<T> Default for $name<T>
+        where
+            T: RealField,
+        {
+            fn default() -> Self {
+                Self::zero()
+            }
+        }
+
+        impl<T> Add for $name<T>
+        where
+            T: RealField,
+        {
+            type Output = Self;
+            fn add(self, other: Self) -> Self {
+                Self {
+                    angular: self.angular + other.angular,
+                    linear: self.linear + other.linear,
+                }
+            }
+        }
+
+        impl<T> AddAssign for $name<T>
+        where
+            T: RealField,
+        {
+            fn add_assign(&mut self, other: Self) {
+                self.angular += other.angular;
+                self.linear += other.linear;
+            }
+        }
+
+        impl<T> Sub for $name<T>
+        where
+            T: RealField,
+        {
+            type Output = Self;
+            fn sub(self, other: Self) -> Self {
+                Self {
+                    angular: self.angular - other.angular,
+                    linear: self.linear - other.linear,
+                }
+            }
+        }
+
+        impl<T> Neg for $name<T>
+        where
+            T: RealField,
+        {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self {
+                    angular: -self.angular,
+                    linear: -self.linear,
+                }
+            }
+        }
+
+        impl<T> Mul<T> for $name<T>
+        where
+            T: RealField,
+        {
+            type Output = Self;
+            fn mul(self, scale: T) -> Self {
+                Self {
+                    angular: self.angular * scale,
+                    linear: self.linear * scale,
+                }
+            }
+        }
+    };
+}
+
+def_spatial_vector!(Motion);
+def_spatial_vector!(Force);
+
+impl<T> Motion<T>
+where
+    T: RealField,
+{
+    /// Change the coordinates by the adjoint of `trans`
+    ///
+    /// `self` is in the frame `B` and `trans` is the pose of `B` in the frame `A`,
+    /// it returns the same motion in the frame `A`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::spatial::Motion;
+    ///
+    /// // rotating around z axis at (1, 0, 0)
+    /// let m = Motion::from_parts(Vector3::zeros(), Vector3::new(0.0, 0.0, 1.0));
+    /// let trans = Isometry3::translation(1.0, 0.0, 0.0);
+    /// let m_world = m.transform(&trans);
+    /// // the origin of the world moves to -y
+    /// assert!((m_world.linear - Vector3::new(0.0, -1.0, 0.0)).norm() < 1e-10);
+    /// assert!((m_world.inverse_transform(&trans).linear).norm() < 1e-10);
+    /// ```
+    pub fn transform(&self, trans: &Isometry3<T>) -> Self {
+        let angular = trans.rotation * self.angular;
+        Self {
+            linear: trans.rotation * self.linear + trans.translation.vector.cross(&angular),
+            angular,
+        }
+    }
+    /// Change the coordinates by the inverse adjoint of `trans`
+    pub fn inverse_transform(&self, trans: &Isometry3<T>) -> Self {
+        Self {
+            linear: trans.rotation.inverse()
+                * (self.linear - trans.translation.vector.cross(&self.angular)),
+            angular: trans.rotation.inverse() * self.angular,
+        }
+    }
+    /// Spatial cross product for motion vectors (`crm`)
+    pub fn cross(&self, other: &Motion<T>) -> Motion<T> {
+        Motion {
+            angular: self.angular.cross(&other.angular),
+            linear: self.angular.cross(&other.linear) + self.linear.cross(&other.angular),
+        }
+    }
+    /// Spatial cross product for force vectors (`crf`)
+    pub fn cross_force(&self, force: &Force<T>) -> Force<T> {
+        Force {
+            angular: self.angular.cross(&force.angular) + self.linear.cross(&force.linear),
+            linear: self.angular.cross(&force.linear),
+        }
+    }
+    /// Power (scalar product of a motion and a force)
+    pub fn dot(&self, force: &Force<T>) -> T {
+        self.angular.dot(&force.angular) + self.linear.dot(&force.linear)
+    }
+    /// Linear velocity of the `point` which is fixed to the moving frame
+    pub fn point_velocity(&self, point: &Vector3<T>) -> Vector3<T> {
+        self.linear + self.angular.cross(point)
+    }
+}
+
+impl<T> Force<T>
+where
+    T: RealField,
+{
+    /// Change the coordinates by the dual adjoint of `trans`
+    ///
+    /// `self` is in the frame `B` and `trans` is the pose of `B` in the frame `A`,
+    /// it returns the same force in the frame `A`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::spatial::Force;
+    ///
+    /// // pushing toward +y at (1, 0, 0)
+    /// let f = Force::from_parts(Vector3::new(0.0, 1.0, 0.0), Vector3::zeros());
+    /// let trans = Isometry3::translation(1.0, 0.0, 0.0);
+    /// let f_world = f.transform(&trans);
+    /// assert!((f_world.angular - Vector3::new(0.0, 0.0, 1.0)).norm() < 1e-10);
+    /// ```
+    pub fn transform(&self, trans: &Isometry3<T>) -> Self {
+        let linear = trans.rotation * self.linear;
+        Self {
+            angular: trans.rotation * self.angular + trans.translation.vector.cross(&linear),
+            linear,
+        }
+    }
+    /// Change the coordinates by the inverse dual adjoint of `trans`
+    pub fn inverse_transform(&self, trans: &Isometry3<T>) -> Self {
+        Self {
+            angular: trans.rotation.inverse()
+                * (self.angular - trans.translation.vector.cross(&self.linear)),
+            linear: trans.rotation.inverse() * self.linear,
+        }
+    }
+    /// Power (scalar product of a motion and a force)
+    pub fn dot(&self, motion: &Motion<T>) -> T {
+        motion.dot(self)
+    }
+}
+
+impl<T> From<Velocity<T>> for Motion<T>
+where
+    T: RealField,
+{
+    fn from(velocity: Velocity<T>) -> Self {
+        Self::from_parts(velocity.translation, velocity.rotation)
+    }
+}
+
+impl<T> From<Motion<T>> for Velocity<T>
+where
+    T: RealField,
+{
+    fn from(motion: Motion<T>) -> Self {
+        Self::from_parts(motion.linear, motion.angular)
+    }
+}
+
+/// Spatial inertia of a rigid body
+///
+/// The rotational inertia is around the center of mass and its axes are the same as the frame.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct SpatialInertia<T: RealField> {
+    /// mass of the body
+    pub mass: T,
+    /// center of mass in the frame
+    pub com: Vector3<T>,
+    /// rotational inertia around the center of mass
+    pub inertia: Matrix3<T>,
+}
+
+impl<T> SpatialInertia<T>
+where
+    T: RealField,
+{
+    pub fn new(mass: T, com: Vector3<T>, inertia: Matrix3<T>) -> Self {
+        Self { mass, com, inertia }
+    }
+    pub fn zero() -> Self {
+        Self::new(T::zero(), Vector3::zeros(), Matrix3::zeros())
+    }
+    /// Rotational inertia around the origin of the frame
+    pub fn inertia_at_origin(&self) -> Matrix3<T> {
+        let c = self.com.cross_matrix();
+        self.inertia - c * c * self.mass
+    }
+    /// Momentum of the body moving with the `motion`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    /// use k::spatial::*;
+    /// use nalgebra::Matrix3;
+    ///
+    /// let inertia = SpatialInertia::new(2.0, Vector3::new(1.0, 0.0, 0.0), Matrix3::zeros());
+    /// let motion = Motion::from_parts(Vector3::zeros(), Vector3::new(0.0, 0.0, 1.0));
+    /// let momentum = inertia.mul_motion(&motion);
+    /// assert!((momentum.linear - Vector3::new(0.0, 2.0, 0.0)).norm() < 1e-10);
+    /// assert!((momentum.angular - Vector3::new(0.0, 0.0, 2.0)).norm() < 1e-10);
+    /// ```
+    pub fn mul_motion(&self, motion: &Motion<T>) -> Force<T> {
+        let linear = (motion.linear + motion.angular.cross(&self.com)) * self.mass;
+        Force {
+            angular: self.inertia * motion.angular + self.com.cross(&linear),
+            linear,
+        }
+    }
+    /// Kinetic energy of the body moving with the `motion`
+    pub fn kinetic_energy(&self, motion: &Motion<T>) -> T {
+        motion.dot(&self.mul_motion(motion)) * na::convert::<_, T>(0.5)
+    }
+    /// Change the coordinates
+    ///
+    /// `self` is in the frame `B` and `trans` is the pose of `B` in the frame `A`,
+    /// it returns the same inertia in the frame `A`.
+    pub fn transform(&self, trans: &Isometry3<T>) -> Self {
+        let rot = trans.rotation.to_rotation_matrix();
+        Self {
+            mass: self.mass,
+            com: trans.transform_point(&Point3::from(self.com)).coords,
+            inertia: rot.matrix() * self.inertia * rot.matrix().transpose(),
+        }
+    }
+    /// 6x6 matrix which maps `[linear, angular]` motion to `[linear, angular]` force
+    pub fn to_matrix(&self) -> Matrix6<T> {
+        let c = self.com.cross_matrix() * self.mass;
+        let mut mat = Matrix6::zeros();
+        mat.fixed_slice_mut::<3, 3>(0, 0)
+            .copy_from(&(Matrix3::identity() * self.mass));
+        mat.fixed_slice_mut::<3, 3>(0, 3).copy_from(&(-c));
+        mat.fixed_slice_mut::<3, 3>(3, 0).copy_from(&c);
+        mat.fixed_slice_mut::<3, 3>(3, 3)
+            .copy_from(&self.inertia_at_origin());
+        mat
+    }
+}
+
+impl<T> Default for SpatialInertia<T>
+where
+    T: RealField,
+{
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<T> Add for SpatialInertia<T>
+where
+    T: RealField,
+{
+    type Output = Self;
+    /// Combine two bodies in the same frame
+    fn add(self, other: Self) -> Self {
+        let mass = self.mass + other.mass;
+        if mass <= T::zero() {
+            return Self::new(mass, Vector3::zeros(), self.inertia + other.inertia);
+        }
+        let com = (self.com * self.mass + other.com * other.mass) / mass;
+        let shift = |body: &Self| {
+            let d = (body.com - com).cross_matrix();
+            body.inertia - d * d * body.mass
+        };
+        Self::new(mass, com, shift(&self) + shift(&other))
+    }
+}
+
+impl<T> AddAssign for SpatialInertia<T>
+where
+    T: RealField,
+{
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T> From<&Inertial<T>> for SpatialInertia<T>
+where
+    T: RealField,
+{
+    fn from(inertial: &Inertial<T>) -> Self {
+        let rot = inertial.origin().rotation.to_rotation_matrix();
+        Self::new(
+            inertial.mass,
+            inertial.origin().translation.vector,
+            rot.matrix() * inertial.inertia * rot.matrix().transpose(),
+        )
+    }
+}
+
+#[test]
+fn test_spatial_transform() {
+    use na::{Translation3, UnitQuaternion};
+    let trans = Isometry3::<f64>::from_parts(
+        Translation3::new(0.1, -0.2, 0.3),
+        UnitQuaternion::from_euler_angles(0.1, 0.2, -0.3),
+    );
+    let m = Motion::from_parts(Vector3::new(0.5, -1.0, 2.0), Vector3::new(0.3, 0.2, -0.1));
+    let f = Force::from_parts(Vector3::new(-1.0, 0.5, 0.2), Vector3::new(0.4, -0.6, 0.1));
+    // power is invariant
+    assert!((m.dot(&f) - m.transform(&trans).dot(&f.transform(&trans))).abs() < 1e-10);
+    assert!(
+        (m.transform(&trans).inverse_transform(&trans) - m)
+            .to_vector()
+            .norm()
+            < 1e-10
+    );
+    assert!(
+        (f.transform(&trans).inverse_transform(&trans) - f)
+            .to_vector()
+            .norm()
+            < 1e-10
+    );
+
+    let inertia = SpatialInertia::new(
+        1.5,
+        Vector3::new(0.1, 0.2, -0.1),
+        Matrix3::new(0.1, 0.01, 0.0, 0.01, 0.2, 0.02, 0.0, 0.02, 0.3),
+    );
+    let by_matrix = inertia.to_matrix() * m.to_vector();
+    assert!((by_matrix - inertia.mul_motion(&m).to_vector()).norm() < 1e-10);
+    // transform of inertia is consistent with the transform of motion and force
+    let h0 = inertia.mul_motion(&m).transform(&trans);
+    let h1 = inertia.transform(&trans).mul_motion(&m.transform(&trans));
+    assert!((h0 - h1).to_vector().norm() < 1e-10);
+    // combined inertia
+    let other = SpatialInertia::new(0.5, Vector3::new(-0.2, 0.0, 0.3), Matrix3::identity() * 0.1);
+    let sum = (inertia + other).mul_motion(&m);
+    let sum_each = inertia.mul_motion(&m) + other.mul_motion(&m);
+    assert!((sum - sum_each).to_vector().norm() < 1e-10);
+}
@@ -0,0 +1,295 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::RealField;
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+
+use super::backend::*;
+use super::chain::*;
+use super::errors::*;
+use super::node::*;
+
+/// Mapping between an actuator and a joint (simple transmission of URDF)
+///
+/// `actuator_position = (joint_position - offset) * mechanical_reduction`.
+/// Negative `mechanical_reduction` means the actuator rotates in the opposite direction.
+///
+/// # Examples
+///
+/// ```
+/// let transmission = k::Transmission::<f64>::new("joint1", "motor1", -50.0, 0.1).unwrap();
+/// assert!((transmission.joint_to_actuator_position(0.3) + 10.0).abs() < 1e-10);
+/// assert!((transmission.actuator_to_joint_position(-10.0) - 0.3).abs() < 1e-10);
+/// // the actuator needs less torque
+/// assert!((transmission.joint_to_actuator_effort(5.0) + 0.1).abs() < 1e-10);
+/// // the actuator must move
+/// assert!(k::Transmission::<f64>::new("joint1", "motor1", 0.0, 0.1).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Transmission<T: RealField> {
+    /// Name of the joint
+    pub joint_name: String,
+    /// Name of the actuator
+    pub actuator_name: String,
+    /// Gear ratio, actuator motion per joint motion
+    pub mechanical_reduction: T,
+    /// Joint position when the actuator position is zero
+    pub offset: T,
+}
+
+impl<T> Transmission<T>
+where
+    T: RealField,
+{
+    /// Create the transmission
+    ///
+    /// It returns `Err` if `mechanical_reduction` is zero, the joint position can not
+    /// be calculated from the actuator position then.
+    pub fn new(
+        joint_name: &str,
+        actuator_name: &str,
+        mechanical_reduction: T,
+        offset: T,
+    ) -> Result<Self, Error> {
+        if mechanical_reduction == T::zero() {
+            return Err(Error::ZeroMechanicalReductionError {
+                joint_name: joint_name.to_owned(),
+            });
+        }
+        Ok(Self {
+            joint_name: joint_name.to_owned(),
+            actuator_name: actuator_name.to_owned(),
+            mechanical_reduction,
+            offset,
+        })
+    }
+
+    pub fn joint_to_actuator_position(&self, position: T) -> T {
+        (position - self.offset) * self.mechanical_reduction
+    }
+
+    pub fn actuator_to_joint_position(&self, position: T) -> T {
+        position / self.mechanical_reduction + self.offset
+    }
+
+    pub fn joint_to_actuator_velocity(&self, velocity: T) -> T {
+        velocity * self.mechanical_reduction
+    }
+
+    pub fn actuator_to_joint_velocity(&self, velocity: T) -> T {
+        velocity / self.mechanical_reduction
+    }
+
+    pub fn joint_to_actuator_effort(&self, effort: T) -> T {
+        effort / self.mechanical_reduction
+    }
+
+    pub fn actuator_to_joint_effort(&self, effort: T) -> T {
+        effort * self.mechanical_reduction
+    }
+}
+
+/// Conversions between the actuator space and the joint space
+///
+/// The actuator values are ordered as `Chain::transmissions()`.
+impl<T, B> Chain<T, B>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    /// The node of the transmission and the index of it in the joint velocities
+    fn transmission_joint(
+        &self,
+        transmission: &Transmission<T>,
+    ) -> Result<(Node<T, B>, usize), Error> {
+        let mut index = 0;
+        for node in self.iter().filter(|node| node.joint().is_movable()) {
+            let joint = node.joint();
+            if joint.name == transmission.joint_name {
+                if joint.joint_type.dof() != 1 {
+                    return Err(Error::SizeMismatchError {
+                        input: 1,
+                        required: joint.joint_type.dof(),
+                    });
+                }
+                return Ok((node.clone(), index));
+            }
+            index += joint.joint_type.dof();
+        }
+        Err(Error::JointNotFoundError {
+            joint_name: transmission.joint_name.to_owned(),
+        })
+    }
+
+    fn check_num_actuators(&self, values: &[T]) -> Result<(), Error> {
+        if values.len() != self.transmissions().len() {
+            return Err(Error::SizeMismatchError {
+                input: values.len(),
+                required: self.transmissions().len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Positions of the actuators calculated from the joint positions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use k::*;
+    ///
+    /// let j0 = NodeBuilder::new()
+    ///     .name("j0")
+    ///     .joint_type(JointType::Rotational{axis: Vector3::z_axis()})
+    ///     .into_node();
+    /// let mut chain = Chain::<f64>::from_root(j0);
+    /// chain.set_transmissions(vec![Transmission::new("j0", "motor0", 100.0, 0.0).unwrap()]);
+    /// chain.set_actuator_positions(&[50.0]).unwrap();
+    /// assert!((chain.joint_positions()[0] - 0.5).abs() < 1e-10);
+    /// assert!((chain.actuator_positions().unwrap()[0] - 50.0).abs() < 1e-10);
+    /// ```
+    pub fn actuator_positions(&self) -> Result<Vec<T>, Error> {
+        self.transmissions()
+            .iter()
+            .map(|transmission| {
+                let (node, _) = self.transmission_joint(transmission)?;
+                let position = node.joint_position().unwrap_or_else(T::zero);
+                Ok(transmission.joint_to_actuator_position(position))
+            })
+            .collect()
+    }
+
+    /// Set the joint positions from the positions of the actuators
+    pub fn set_actuator_positions(&self, positions: &[T]) -> Result<(), Error> {
+        self.check_num_actuators(positions)?;
+        for (transmission, position) in self.transmissions().iter().zip(positions) {
+            let (node, _) = self.transmission_joint(transmission)?;
+            node.set_joint_position(transmission.actuator_to_joint_position(*position))?;
+        }
+        Ok(())
+    }
+
+    /// Velocities of the actuators calculated from the joint velocities
+    pub fn actuator_velocities(&self) -> Result<Vec<T>, Error> {
+        self.transmissions()
+            .iter()
+            .map(|transmission| {
+                let (node, _) = self.transmission_joint(transmission)?;
+                let velocity = node.joint().joint_velocity().unwrap_or_else(T::zero);
+                Ok(transmission.joint_to_actuator_velocity(velocity))
+            })
+            .collect()
+    }
+
+    /// Set the joint velocities from the velocities of the actuators
+    pub fn set_actuator_velocities(&self, velocities: &[T]) -> Result<(), Error> {
+        self.check_num_actuators(velocities)?;
+        for (transmission, velocity) in self.transmissions().iter().zip(velocities) {
+            let (node, _) = self.transmission_joint(transmission)?;
+            node.set_joint_velocity(transmission.actuator_to_joint_velocity(*velocity))?;
+        }
+        Ok(())
+    }
+
+    /// Convert the joint efforts (length is `dof()`) to the actuator efforts
+    pub fn actuator_efforts_from_joint_efforts(
+        &self,
+        joint_efforts: &[T],
+    ) -> Result<Vec<T>, Error> {
+        if joint_efforts.len() != self.dof() {
+            return Err(Error::SizeMismatchError {
+                input: joint_efforts.len(),
+                required: self.dof(),
+            });
+        }
+        self.transmissions()
+            .iter()
+            .map(|transmission| {
+                let (_, index) = self.transmission_joint(transmission)?;
+                Ok(transmission.joint_to_actuator_effort(joint_efforts[index]))
+            })
+            .collect()
+    }
+
+    /// Convert the actuator efforts to the joint efforts (length is `dof()`)
+    ///
+    /// The efforts of the joints without actuators are zero.
+    pub fn joint_efforts_from_actuator_efforts(
+        &self,
+        actuator_efforts: &[T],
+    ) -> Result<Vec<T>, Error> {
+        self.check_num_actuators(actuator_efforts)?;
+        let mut joint_efforts = vec![T::zero(); self.dof()];
+        for (transmission, effort) in self.transmissions().iter().zip(actuator_efforts) {
+            let (_, index) = self.transmission_joint(transmission)?;
+            joint_efforts[index] += transmission.actuator_to_joint_effort(*effort);
+        }
+        Ok(joint_efforts)
+    }
+}
+
+#[test]
+fn test_actuator_mapping() {
+    use super::joint::*;
+    use na::Vector3;
+    let j0 = NodeBuilder::new()
+        .name("j0")
+        .joint_type(JointType::Rotational {
+            axis: Vector3::z_axis(),
+        })
+        .into_node();
+    let j1 = NodeBuilder::new()
+        .name("j1")
+        .joint_type(JointType::Linear {
+            axis: Vector3::x_axis(),
+        })
+        .into_node();
+    let j2 = NodeBuilder::new()
+        .name("j2")
+        .joint_type(JointType::Rotational {
+            axis: Vector3::y_axis(),
+        })
+        .into_node();
+    j1.set_parent(&j0);
+    j2.set_parent(&j1);
+    let mut chain = Chain::<f64>::from_root(j0);
+    chain.set_transmissions(vec![
+        Transmission::new("j2", "m2", -10.0, 0.5).unwrap(),
+        Transmission::new("j0", "m0", 2.0, 0.0).unwrap(),
+    ]);
+    chain.set_joint_positions(&[0.1, 0.2, 0.3]).unwrap();
+    let positions = chain.actuator_positions().unwrap();
+    assert!((positions[0] - 2.0).abs() < 1e-10);
+    assert!((positions[1] - 0.2).abs() < 1e-10);
+
+    chain.set_actuator_velocities(&[5.0, 1.0]).unwrap();
+    assert_eq!(chain.joint_velocities(), vec![0.5, 0.0, -0.5]);
+    let velocities = chain.actuator_velocities().unwrap();
+    assert!((velocities[0] - 5.0).abs() < 1e-10);
+
+    let joint_efforts = chain
+        .joint_efforts_from_actuator_efforts(&[1.0, 3.0])
+        .unwrap();
+    assert_eq!(joint_efforts, vec![6.0, 0.0, -10.0]);
+    let actuator_efforts = chain
+        .actuator_efforts_from_joint_efforts(&joint_efforts)
+        .unwrap();
+    assert_eq!(actuator_efforts, vec![1.0, 3.0]);
+    assert!(chain.set_actuator_positions(&[0.0]).is_err());
+
+    chain.set_transmissions(vec![Transmission::new("no_joint", "m", 1.0, 0.0).unwrap()]);
+    assert!(chain.actuator_positions().is_err());
+}
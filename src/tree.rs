@@ -0,0 +1,404 @@
+/*
+  Copyright 2020 Takashi Ogura
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+use na::{Isometry3, RealField};
+use nalgebra as na;
+use simba::scalar::SubsetOf;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use super::backend::*;
+use super::chain::*;
+use super::errors::*;
+use super::joint::*;
+use super::link::*;
+use super::node::*;
+
+/// Handle of a node in `Tree`
+///
+/// It is just the index of the node, so it is `Copy` and valid only for the `Tree`
+/// which created it (and its clones).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// The index of the node in `Tree`
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TreeNode<T: RealField> {
+    joint: Joint<T>,
+    link: Option<Link<T>>,
+    payload: Option<Inertial<T>>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    mimic_parents: Vec<NodeId>,
+    mimic_children: Vec<NodeId>,
+    coupling: Option<Arc<dyn Coupling<T>>>,
+}
+
+/// Kinematic tree whose nodes are stored in one `Vec`
+///
+/// The nodes are referenced by `NodeId`s instead of `Arc` and `Weak` pointers, so there
+/// are no locks and no reference cycles, the parent and the children are found in O(1)
+/// and `clone()` copies the whole tree at once. A parent is always added before its
+/// children, so the ids of the parents are smaller than the ids of the children.
+///
+/// It can be converted from and to `Chain`.
+///
+/// # Examples
+///
+/// ```
+/// use k::*;
+///
+/// let mut tree = Tree::new();
+/// let root = tree.add_node(Joint::new("root", JointType::Fixed), None);
+/// let j0 = tree.add_node(
+///     NodeBuilder::new()
+///         .name("j0")
+///         .translation(Translation3::new(0.0, 0.0, 1.0))
+///         .joint_type(JointType::Linear{axis: Vector3::x_axis()})
+///         .finalize(),
+///     Some(root),
+/// );
+/// assert_eq!(tree.parent(j0), Some(root));
+/// assert_eq!(tree.children(root), &[j0]);
+/// assert_eq!(tree.find("j0"), Some(j0));
+///
+/// tree.set_joint_position(j0, 0.5).unwrap();
+/// let transforms = tree.update_transforms();
+/// assert_eq!(transforms[j0.index()].translation.vector, Vector3::new(0.5, 0.0, 1.0));
+///
+/// let chain = tree.to_chain();
+/// assert_eq!(chain.joint_positions(), vec![0.5]);
+/// assert_eq!(Tree::from_chain(&chain).unwrap().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tree<T: RealField> {
+    nodes: Vec<TreeNode<T>>,
+}
+
+impl<T> Tree<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+    /// Add a node under `parent`, or as a root if `parent` is `None`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is not a node of this tree.
+    pub fn add_node(&mut self, joint: Joint<T>, parent: Option<NodeId>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        self.nodes.push(TreeNode {
+            joint,
+            link: None,
+            payload: None,
+            parent,
+            children: Vec::new(),
+            mimic_parents: Vec::new(),
+            mimic_children: Vec::new(),
+            coupling: None,
+        });
+        id
+    }
+    /// Number of the nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    /// `true` if there are no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+    /// Iterate over all the ids, the parents come before their children
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+    /// Iterate over the nodes without parents
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.ids()
+            .filter(move |id| self.nodes[id.0].parent.is_none())
+    }
+    /// Find the node by the joint name
+    pub fn find(&self, joint_name: &str) -> Option<NodeId> {
+        self.ids()
+            .find(|id| self.nodes[id.0].joint.name == joint_name)
+    }
+    /// The parent of the node, `None` for the roots
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+    /// The children of the node in the order they were added
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+    /// The joint of the node
+    pub fn joint(&self, id: NodeId) -> &Joint<T> {
+        &self.nodes[id.0].joint
+    }
+    /// Mutable reference to the joint
+    ///
+    /// The positions of the mimic children are not updated by the changes through it,
+    /// use `set_joint_position()` for that.
+    pub fn joint_mut(&mut self, id: NodeId) -> &mut Joint<T> {
+        &mut self.nodes[id.0].joint
+    }
+    /// The link of the node, which is attached after the joint
+    pub fn link(&self, id: NodeId) -> Option<&Link<T>> {
+        self.nodes[id.0].link.as_ref()
+    }
+    /// Set or clear the link of the node
+    pub fn set_link(&mut self, id: NodeId, link: Option<Link<T>>) {
+        self.nodes[id.0].link = link;
+    }
+    /// The additional mass attached to the node, e.g. the grasped object
+    pub fn payload(&self, id: NodeId) -> Option<&Inertial<T>> {
+        self.nodes[id.0].payload.as_ref()
+    }
+    /// Attach (or detach by `None`) an additional mass to the node
+    ///
+    /// It is the same as `Node::set_payload()`, the origin is the offset from the node.
+    pub fn set_payload(&mut self, id: NodeId, payload: Option<Inertial<T>>) {
+        self.nodes[id.0].payload = payload;
+    }
+    /// The joints which are the inputs of the coupling of the joint
+    pub fn mimic_parents(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].mimic_parents
+    }
+    /// The coupling to calculate the position of the joint
+    pub fn coupling(&self, id: NodeId) -> Option<&Arc<dyn Coupling<T>>> {
+        self.nodes[id.0].coupling.as_ref()
+    }
+    /// Make the joint follow the positions of `parents` by `coupling`
+    ///
+    /// It is the same as `Node::set_coupling()`, but it returns `CircularCouplingError`
+    /// and keeps the old coupling if the joint is one of the inputs of `parents`.
+    pub fn set_coupling<C>(
+        &mut self,
+        id: NodeId,
+        parents: &[NodeId],
+        coupling: C,
+    ) -> Result<(), Error>
+    where
+        C: Coupling<T> + 'static,
+    {
+        self.set_coupling_arc(id, parents, Arc::new(coupling))
+    }
+
+    fn set_coupling_arc(
+        &mut self,
+        id: NodeId,
+        parents: &[NodeId],
+        coupling: Arc<dyn Coupling<T>>,
+    ) -> Result<(), Error> {
+        if self.is_mimic_ancestor(id, parents) {
+            return Err(Error::CircularCouplingError {
+                joint_name: self.nodes[id.0].joint.name.clone(),
+            });
+        }
+        for parent in std::mem::take(&mut self.nodes[id.0].mimic_parents) {
+            self.nodes[parent.0]
+                .mimic_children
+                .retain(|child| *child != id);
+        }
+        for parent in parents {
+            self.nodes[parent.0].mimic_children.push(id);
+        }
+        let node = &mut self.nodes[id.0];
+        node.mimic_parents = parents.to_vec();
+        node.coupling = Some(coupling);
+        Ok(())
+    }
+
+    /// True if `id` is one of `parents` or their mimic parents, recursively
+    fn is_mimic_ancestor(&self, id: NodeId, parents: &[NodeId]) -> bool {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack = parents.to_vec();
+        while let Some(parent) = stack.pop() {
+            if parent == id {
+                return true;
+            }
+            if !visited[parent.0] {
+                visited[parent.0] = true;
+                stack.extend_from_slice(&self.nodes[parent.0].mimic_parents);
+            }
+        }
+        false
+    }
+    /// Set the position of the joint and update its mimic children
+    ///
+    /// It does nothing for the mimic joints, like `Node::set_joint_position()`.
+    pub fn set_joint_position(&mut self, id: NodeId, position: T) -> Result<(), Error> {
+        if !self.nodes[id.0].mimic_parents.is_empty() {
+            return Ok(());
+        }
+        self.nodes[id.0].joint.set_joint_position(position)?;
+        self.update_mimic_children(id)
+    }
+
+    fn update_mimic_children(&mut self, id: NodeId) -> Result<(), Error> {
+        for child in self.nodes[id.0].mimic_children.clone() {
+            let node = &self.nodes[child.0];
+            if let Some(coupling) = &node.coupling {
+                let positions = node
+                    .mimic_parents
+                    .iter()
+                    .map(|parent| {
+                        self.nodes[parent.0]
+                            .joint
+                            .joint_position()
+                            .unwrap_or_else(T::zero)
+                    })
+                    .collect::<Vec<_>>();
+                let position = coupling.position(&positions);
+                self.nodes[child.0].joint.set_joint_position(position)?;
+            }
+            self.update_mimic_children(child)?;
+        }
+        Ok(())
+    }
+    /// Calculate the world transforms of all the nodes, indexed by `NodeId::index()`
+    pub fn update_transforms(&self) -> Vec<Isometry3<T>> {
+        let mut transforms: Vec<Isometry3<T>> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let local = node.joint.local_transform();
+            let world = match node.parent {
+                Some(parent) => transforms[parent.0] * local,
+                None => local,
+            };
+            transforms.push(world);
+        }
+        transforms
+    }
+    /// Create the tree from the nodes of `chain`
+    ///
+    /// The parents and the mimic parents which are out of `chain` are ignored.
+    /// The passive nodes, the loop closures and the transmissions are not copied.
+    /// It returns `CircularCouplingError` if the mimic joints of `chain` are coupled in a circle.
+    pub fn from_chain<B: NodeBackend<T>>(chain: &Chain<T, B>) -> Result<Self, Error> {
+        let mut tree = Self::new();
+        let mut id_of = HashMap::new();
+        for node in chain.iter() {
+            let parent = node
+                .parent()
+                .and_then(|parent| id_of.get(&parent.as_ptr()).copied());
+            let id = tree.add_node(node.joint().clone(), parent);
+            tree.set_link(id, node.link().clone());
+            tree.set_payload(id, node.payload());
+            id_of.insert(node.as_ptr(), id);
+        }
+        for (index, node) in chain.iter().enumerate() {
+            let id = NodeId(index);
+            if let Some(coupling) = node.coupling() {
+                let parents = node
+                    .mimic_parents()
+                    .iter()
+                    .map(|parent| id_of.get(&parent.as_ptr()).copied())
+                    .collect::<Option<Vec<_>>>();
+                if let Some(parents) = parents {
+                    tree.set_coupling_arc(id, &parents, coupling)?;
+                }
+            }
+        }
+        Ok(tree)
+    }
+    /// Create new `Node`s and `Chain` from the first root of the tree
+    ///
+    /// The nodes use the default backend, `Chain::to_backend()` converts them.
+    /// The nodes which are not the descendants of the first root are not included.
+    /// The order of the nodes is kept, so the joint positions of a chain are the same
+    /// after converting it to `Tree` and back.
+    pub fn to_chain(&self) -> Chain<T> {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|tree_node| {
+                let node = Node::new(tree_node.joint.clone());
+                node.set_link(tree_node.link.clone());
+                node.set_payload(tree_node.payload.clone());
+                node
+            })
+            .collect::<Vec<_>>();
+        for (node, tree_node) in nodes.iter().zip(&self.nodes) {
+            if let Some(parent) = tree_node.parent {
+                node.set_parent(&nodes[parent.0]);
+            }
+            if let Some(coupling) = &tree_node.coupling {
+                let parents = tree_node
+                    .mimic_parents
+                    .iter()
+                    .map(|parent| &nodes[parent.0])
+                    .collect::<Vec<_>>();
+                node.set_coupling_arc(&parents, coupling.clone());
+            }
+        }
+        // keep the order of the tree, the parents always come before their children
+        let first_root = self.roots().next();
+        let mut in_chain = vec![false; self.nodes.len()];
+        for (i, tree_node) in self.nodes.iter().enumerate() {
+            in_chain[i] = match tree_node.parent {
+                Some(parent) => in_chain[parent.0],
+                None => first_root == Some(NodeId(i)),
+            };
+        }
+        Chain::from_nodes(
+            nodes
+                .into_iter()
+                .zip(in_chain)
+                .filter_map(|(node, in_chain)| if in_chain { Some(node) } else { None })
+                .collect(),
+        )
+    }
+}
+
+impl<T> Default for Tree<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, B> TryFrom<&Chain<T, B>> for Tree<T>
+where
+    T: RealField + SubsetOf<f64>,
+    B: NodeBackend<T>,
+{
+    type Error = Error;
+
+    fn try_from(chain: &Chain<T, B>) -> Result<Self, Error> {
+        Self::from_chain(chain)
+    }
+}
+
+impl<T> From<&Tree<T>> for Chain<T>
+where
+    T: RealField + SubsetOf<f64>,
+{
+    fn from(tree: &Tree<T>) -> Self {
+        tree.to_chain()
+    }
+}
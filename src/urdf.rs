@@ -17,9 +17,11 @@
 //!
 
 use super::chain::*;
+use super::errors::*;
 use super::joint::*;
 use super::link::*;
 use super::node::*;
+use super::transmission::*;
 use log::*;
 use na::{Isometry3, Matrix3, RealField};
 use nalgebra as na;
@@ -222,28 +224,61 @@ where
     T: RealField + SubsetOf<f64>,
 {
     fn from(joint: &urdf_rs::Joint) -> Joint<T> {
-        let limit = if (joint.limit.upper - joint.limit.lower) == 0.0 {
-            None
-        } else {
-            Some(Range::new(
+        let limit = match joint.joint_type {
+            // continuous joint has no position limits
+            urdf_rs::JointType::Continuous => None,
+            _ if (joint.limit.upper - joint.limit.lower) == 0.0 => None,
+            _ => Some(Range::new(
                 na::convert(joint.limit.lower),
                 na::convert(joint.limit.upper),
+            )),
+        };
+        // soft limits are not given if they are the same (default is zero)
+        let safety = &joint.safety_controller;
+        let safety_controller = if safety.soft_lower_limit < safety.soft_upper_limit {
+            Some(SafetyController::new(
+                Range::new(
+                    na::convert(safety.soft_lower_limit),
+                    na::convert(safety.soft_upper_limit),
+                ),
+                na::convert(safety.k_position),
+                na::convert(safety.k_velocity),
             ))
+        } else {
+            None
+        };
+        let velocity_limit = if joint.limit.velocity > 0.0 {
+            Some(na::convert(joint.limit.velocity))
+        } else {
+            None
+        };
+        let effort_limit = if joint.limit.effort > 0.0 {
+            Some(na::convert(joint.limit.effort))
+        } else {
+            None
         };
         NodeBuilder::<T>::new()
             .name(&joint.name)
             .joint_type(match joint.joint_type {
-                urdf_rs::JointType::Revolute | urdf_rs::JointType::Continuous => {
-                    JointType::Rotational {
-                        axis: axis_from(joint.axis.xyz),
-                    }
-                }
+                urdf_rs::JointType::Revolute => JointType::Rotational {
+                    axis: axis_from(joint.axis.xyz),
+                },
+                urdf_rs::JointType::Continuous => JointType::Continuous {
+                    axis: axis_from(joint.axis.xyz),
+                },
                 urdf_rs::JointType::Prismatic => JointType::Linear {
                     axis: axis_from(joint.axis.xyz),
                 },
+                urdf_rs::JointType::Planar => JointType::Planar {
+                    normal: axis_from(joint.axis.xyz),
+                },
+                urdf_rs::JointType::Floating => JointType::Floating,
                 _ => JointType::Fixed,
             })
             .limits(limit)
+            .safety_controller(safety_controller)
+            .velocity_limit(velocity_limit)
+            .effort_limit(effort_limit)
             .rotation(quaternion_from(&joint.origin.rpy))
             .translation(translation_from(&joint.origin.xyz))
             .finalize()
@@ -325,12 +360,167 @@ impl<T> Chain<T>
 where
     T: RealField + SubsetOf<f64>,
 {
+    /// Create `Chain` from the URDF file
+    ///
+    /// The file is expanded by xacro if the extension is `.xacro`.
+    /// The simple transmissions in the file are loaded as `Chain::transmissions()`.
     pub fn from_urdf_file<P>(path: P) -> Result<Self, urdf_rs::UrdfError>
     where
         P: AsRef<Path>,
     {
-        Ok(urdf_rs::read_file(path)?.into())
+        let urdf = match path.as_ref().extension() {
+            Some(ext) if ext == "xacro" => urdf_rs::utils::convert_xacro_to_urdf(&path)?,
+            _ => std::fs::read_to_string(&path)?,
+        };
+        Self::from_urdf_str(&urdf)
+    }
+
+    /// Create `Chain` from the string of URDF
+    ///
+    /// The simple transmissions are loaded as `Chain::transmissions()`.
+    /// `urdf_rs::Robot` does not have the transmissions, so `Chain::from(robot)` has no
+    /// transmissions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let urdf = r#"
+    /// <robot name="robo">
+    ///   <link name="base"/>
+    ///   <link name="arm"/>
+    ///   <joint name="joint1" type="continuous">
+    ///     <parent link="base"/>
+    ///     <child link="arm"/>
+    ///   </joint>
+    ///   <transmission name="trans1">
+    ///     <joint name="joint1"/>
+    ///     <actuator name="motor1"/>
+    ///     <mechanicalReduction>10</mechanicalReduction>
+    ///   </transmission>
+    /// </robot>"#;
+    /// let chain = k::Chain::<f64>::from_urdf_str(urdf).unwrap();
+    /// assert_eq!(chain.dof(), 1);
+    /// assert_eq!(chain.transmissions()[0].actuator_name, "motor1");
+    /// ```
+    pub fn from_urdf_str(urdf: &str) -> Result<Self, urdf_rs::UrdfError> {
+        let mut chain: Self = urdf_rs::read_from_string(urdf)?.into();
+        let transmissions = transmissions_from_str(urdf)
+            .map_err(|err| urdf_rs::UrdfError::Parse(err.to_string()))?;
+        chain.set_transmissions(transmissions);
+        Ok(chain)
+    }
+}
+
+/// `<transmission>` of URDF which is being parsed
+#[derive(Default)]
+struct TransmissionElement {
+    name: String,
+    joints: Vec<String>,
+    actuators: Vec<String>,
+    mechanical_reduction: Option<f64>,
+    offset: Option<f64>,
+}
+
+/// Read `<transmission>` elements of URDF
+///
+/// `urdf_rs` does not parse them. Only the simple transmissions (one joint and one actuator)
+/// are supported, the others are ignored. `<mechanicalReduction>` can be in `<actuator>`
+/// or in `<transmission>`, and `<offset>` is in `<joint>`.
+/// It returns `Err` if the XML is broken or the mechanical reduction is zero.
+///
+/// # Examples
+///
+/// ```
+/// let urdf = r#"
+/// <robot name="robo">
+///   <transmission name="trans1">
+///     <type>transmission_interface/SimpleTransmission</type>
+///     <joint name="joint1">
+///       <offset>0.5</offset>
+///     </joint>
+///     <actuator name="motor1">
+///       <mechanicalReduction>-50</mechanicalReduction>
+///     </actuator>
+///   </transmission>
+/// </robot>"#;
+/// let transmissions = k::urdf::transmissions_from_str::<f64>(urdf).unwrap();
+/// assert_eq!(transmissions.len(), 1);
+/// assert_eq!(transmissions[0].joint_name, "joint1");
+/// assert_eq!(transmissions[0].actuator_name, "motor1");
+/// assert_eq!(transmissions[0].mechanical_reduction, -50.0);
+/// assert_eq!(transmissions[0].offset, 0.5);
+/// ```
+pub fn transmissions_from_str<T>(urdf: &str) -> Result<Vec<Transmission<T>>, Error>
+where
+    T: RealField,
+{
+    use xml::reader::{EventReader, XmlEvent};
+    let mut transmissions = Vec::new();
+    let mut element = TransmissionElement::default();
+    // names of the current element and its parents
+    let mut path: Vec<String> = Vec::new();
+    for event in EventReader::from_str(urdf) {
+        let event = event.map_err(|err| Error::TransmissionParseError {
+            message: err.to_string(),
+        })?;
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let name_attribute = attributes
+                    .iter()
+                    .find(|attribute| attribute.name.local_name == "name")
+                    .map(|attribute| attribute.value.to_owned())
+                    .unwrap_or_default();
+                let parents = path.iter().map(String::as_str).collect::<Vec<_>>();
+                match (parents.as_slice(), name.local_name.as_str()) {
+                    (["robot"], "transmission") => {
+                        element = TransmissionElement {
+                            name: name_attribute,
+                            ..Default::default()
+                        }
+                    }
+                    (["robot", "transmission"], "joint") => element.joints.push(name_attribute),
+                    (["robot", "transmission"], "actuator") => {
+                        element.actuators.push(name_attribute)
+                    }
+                    _ => {}
+                }
+                path.push(name.local_name);
+            }
+            XmlEvent::Characters(text) => {
+                let parents = path.iter().map(String::as_str).collect::<Vec<_>>();
+                match parents.as_slice() {
+                    ["robot", "transmission", "mechanicalReduction"]
+                    | ["robot", "transmission", "actuator", "mechanicalReduction"] => {
+                        element.mechanical_reduction = text.trim().parse().ok()
+                    }
+                    ["robot", "transmission", "joint", "offset"] => {
+                        element.offset = text.trim().parse().ok()
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::EndElement { .. } => {
+                let ended = path.pop();
+                if path.len() == 1 && ended.as_deref() == Some("transmission") {
+                    let element = std::mem::take(&mut element);
+                    if element.joints.len() == 1 && element.actuators.len() == 1 {
+                        transmissions.push(Transmission::new(
+                            &element.joints[0],
+                            &element.actuators[0],
+                            na::convert(element.mechanical_reduction.unwrap_or(1.0)),
+                            na::convert(element.offset.unwrap_or(0.0)),
+                        )?);
+                    } else {
+                        warn!("transmission {} is not simple, ignored", element.name);
+                    }
+                }
+            }
+            _ => {}
+        }
     }
+    Ok(transmissions)
 }
 
 /// Useful function to deal about 'Links' of URDF
@@ -400,3 +590,57 @@ fn test_tree_from_file() {
     assert_eq!(names[0], "root");
     assert_eq!(names[1], "r_shoulder_yaw");
 }
+
+#[test]
+fn test_transmissions() {
+    let urdf = r#"
+<robot name="robo">
+  <joint name="joint1" type="revolute"/>
+  <transmission name="trans1">
+    <type>transmission_interface/SimpleTransmission</type>
+    <joint name="joint1"/>
+    <actuator name="motor1"/>
+    <mechanicalReduction>20</mechanicalReduction>
+  </transmission>
+  <transmission name="differential">
+    <type>transmission_interface/DifferentialTransmission</type>
+    <joint name="joint2"/>
+    <joint name="joint3"/>
+    <actuator name="motor2"/>
+    <actuator name="motor3"/>
+  </transmission>
+</robot>"#;
+    let transmissions = transmissions_from_str::<f64>(urdf).unwrap();
+    assert_eq!(transmissions.len(), 1);
+    assert_eq!(transmissions[0].joint_name, "joint1");
+    assert_eq!(transmissions[0].mechanical_reduction, 20.0);
+    assert_eq!(transmissions[0].offset, 0.0);
+    assert!(transmissions_from_str::<f64>("<robot>").is_err());
+    let zero_reduction = urdf.replace(">20<", ">0<");
+    match transmissions_from_str::<f64>(&zero_reduction) {
+        Err(Error::ZeroMechanicalReductionError { joint_name }) => assert_eq!(joint_name, "joint1"),
+        _ => panic!("zero reduction must be an error"),
+    }
+    // sample.urdf has no transmissions
+    let tree = Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    assert!(tree.transmissions().is_empty());
+}
+
+#[test]
+fn test_velocity_effort_limits() {
+    let tree = Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+    for joint in tree.iter_joints() {
+        assert_eq!(joint.velocity_limit, Some(1.0));
+        // effort="0" means no limit
+        assert!(joint.effort_limit.is_none());
+    }
+    let mut velocities = vec![0.5; tree.dof()];
+    assert!(tree.check_joint_velocities(&velocities).is_ok());
+    velocities[3] = -1.5;
+    match tree.check_joint_velocities(&velocities) {
+        Err(crate::Error::VelocityOutOfLimitError { joint_name, .. }) => {
+            assert_eq!(joint_name, tree.iter_joints().nth(3).unwrap().name)
+        }
+        _ => panic!("velocity limit must be checked"),
+    }
+}
@@ -43,4 +43,329 @@ mod tests {
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    pub fn test_incremental_update_transforms() {
+        let tree = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        let pos = vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, -0.1, -0.2];
+        tree.set_joint_positions(&pos).unwrap();
+        tree.update_transforms();
+        let elbow = tree.find("r_elbow_pitch").unwrap();
+        elbow.set_joint_position(-0.5).unwrap();
+        // the moved joint and its descendants are cleared
+        for node in elbow.iter_descendants() {
+            assert!(node.world_transform().is_none());
+        }
+        // the other arm and the ancestors are still valid
+        assert!(tree
+            .find("l_wrist_pitch")
+            .unwrap()
+            .world_transform()
+            .is_some());
+        assert!(tree
+            .find("r_shoulder_roll")
+            .unwrap()
+            .world_transform()
+            .is_some());
+
+        let transforms = tree.update_transforms();
+        let expected_tree = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        expected_tree
+            .set_joint_positions(&tree.joint_positions())
+            .unwrap();
+        let expected = expected_tree.update_transforms();
+        for (trans, expected) in transforms.iter().zip(expected.iter()) {
+            assert!((trans.translation.vector - expected.translation.vector).norm() < 1e-10);
+            assert!(trans.rotation.angle_to(&expected.rotation) < 1e-10);
+        }
+
+        // moving the origin of the root invalidates all
+        tree.set_origin(k::Isometry3::translation(0.0, 0.0, 1.0));
+        assert!(tree.iter().all(|node| node.world_transform().is_none()));
+        let moved = tree.update_transforms();
+        assert!(
+            (moved[12].translation.vector.z - expected[12].translation.vector.z - 1.0).abs()
+                < 1e-10
+        );
+    }
+
+    #[test]
+    pub fn test_find_by_index() {
+        let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        for node in chain.iter() {
+            let name = node.joint().name.clone();
+            assert!(chain.find(&name).unwrap() == node);
+            let link_name = node.link().as_ref().map(|link| link.name.clone());
+            if let Some(link_name) = link_name {
+                assert!(chain.find_link(&link_name).unwrap() == node);
+            }
+        }
+        // the links set after creating the chain are found, too
+        let node = chain.find("l_shoulder_yaw").unwrap();
+        node.set_link(Some(k::link::LinkBuilder::new().name("renamed").finalize()));
+        assert!(chain.find_link("renamed").unwrap() == node);
+
+        let cloned = chain.clone();
+        let handle = cloned.joint_handle("r_elbow_pitch").unwrap();
+        handle.set_position(-0.5).unwrap();
+        assert_eq!(handle.position(), -0.5);
+        assert_eq!(
+            cloned.joint_positions()[handle.position_index().unwrap()],
+            -0.5
+        );
+        assert!(handle.set_position(100.0).is_err());
+        assert!(cloned.joint_handle("root").is_none());
+    }
+
+    #[test]
+    pub fn test_joint_positions_by_name() {
+        let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        let mut named = chain
+            .joint_positions_by_name()
+            .into_iter()
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(named.len(), chain.dof());
+        for position in named.values_mut() {
+            *position = 0.2;
+        }
+        chain.set_joint_positions_by_name(named).unwrap();
+        assert_eq!(chain.joint_positions(), vec![0.2; chain.dof()]);
+
+        // out of the limit, nothing is changed
+        assert!(chain
+            .set_joint_positions_by_name(vec![("l_elbow_pitch", 0.3), ("r_elbow_pitch", 10.0)])
+            .is_err());
+        assert_eq!(chain.joint_positions(), vec![0.2; chain.dof()]);
+        match chain.set_joint_positions_by_name(vec![("a", 0.0), ("root", 0.0), ("b", 0.0)]) {
+            Err(k::Error::UnknownJointNamesError { joint_names }) => {
+                assert_eq!(joint_names, vec!["a", "b"])
+            }
+            _ => panic!("unknown names must be an error"),
+        }
+        assert!(chain
+            .set_joint_positions_by_name(vec![("root", 0.0)])
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_joint_positions_by_name_mimic() {
+        let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        let r_wrist_yaw = chain.find("r_wrist_yaw").unwrap();
+        r_wrist_yaw.set_mimic_parent(
+            chain.find("l_wrist_yaw").unwrap(),
+            k::joint::Mimic::new(2.0, 0.0),
+        );
+        // the value for the mimic joint is ignored
+        chain
+            .set_joint_positions_by_name(vec![("l_wrist_yaw", 0.4), ("r_wrist_yaw", 10.0)])
+            .unwrap();
+        assert_eq!(r_wrist_yaw.joint_position(), Some(0.8));
+        // r_wrist_yaw would be 1.2, out of its limit
+        assert!(chain
+            .set_joint_positions_by_name(vec![("l_elbow_pitch", 0.3), ("l_wrist_yaw", 0.6)])
+            .is_err());
+        assert_eq!(
+            chain.find("l_elbow_pitch").unwrap().joint_position(),
+            Some(0.0)
+        );
+        assert_eq!(
+            chain.find("l_wrist_yaw").unwrap().joint_position(),
+            Some(0.4)
+        );
+        assert_eq!(r_wrist_yaw.joint_position(), Some(0.8));
+    }
+
+    #[test]
+    pub fn test_joint_positions_by_name_two_level_mimic() {
+        let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        let r_elbow_pitch = chain.find("r_elbow_pitch").unwrap();
+        let r_wrist_yaw = chain.find("r_wrist_yaw").unwrap();
+        r_elbow_pitch.set_mimic_parent(
+            chain.find("l_wrist_yaw").unwrap(),
+            k::joint::Mimic::new(1.0, 0.0),
+        );
+        r_wrist_yaw.set_mimic_parent(r_elbow_pitch, k::joint::Mimic::new(2.0, 0.0));
+        chain
+            .set_joint_positions_by_name(vec![("l_wrist_yaw", 0.4)])
+            .unwrap();
+        assert_eq!(r_elbow_pitch.joint_position(), Some(0.4));
+        assert_eq!(r_wrist_yaw.joint_position(), Some(0.8));
+        // r_elbow_pitch is 0.6 in its limit, but r_wrist_yaw would be 1.2 out of its limit
+        assert!(chain
+            .set_joint_positions_by_name(vec![("l_elbow_pitch", 0.3), ("l_wrist_yaw", 0.6)])
+            .is_err());
+        assert_eq!(
+            chain.find("l_elbow_pitch").unwrap().joint_position(),
+            Some(0.0)
+        );
+        assert_eq!(
+            chain.find("l_wrist_yaw").unwrap().joint_position(),
+            Some(0.4)
+        );
+        assert_eq!(r_elbow_pitch.joint_position(), Some(0.4));
+        assert_eq!(r_wrist_yaw.joint_position(), Some(0.8));
+    }
+
+    fn create_gripper() -> k::Chain<f64> {
+        let base = k::NodeBuilder::new().name("gripper_base").into_node();
+        let finger = |name: &str, y: f64| {
+            k::NodeBuilder::new()
+                .name(name)
+                .translation(k::Translation3::new(0.0, y, -0.05))
+                .joint_type(k::JointType::Linear {
+                    axis: k::Vector3::y_axis(),
+                })
+                .limits(Some((-0.05..=0.05).into()))
+                .into_node()
+        };
+        let finger_l = finger("finger_l", 0.05);
+        let finger_r = finger("finger_r", -0.05);
+        finger_l.set_parent(&base);
+        finger_r.set_parent(&base);
+        finger_r.set_mimic_parent(&finger_l, k::joint::Mimic::new(-1.0, 0.0));
+        k::Chain::from_root(base)
+    }
+
+    #[test]
+    pub fn test_attach_detach() {
+        let mut arm = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        let positions = arm.joint_positions();
+        let num_nodes = arm.iter().count();
+        let wrist = arm.find("r_wrist_pitch").unwrap().clone();
+        let mount = k::Isometry3::translation(0.0, 0.0, -0.1);
+
+        // the nodes and the names must not be shared
+        let elbow = arm.find("r_elbow_pitch").unwrap().clone();
+        let elbow_parent = elbow.parent();
+        match arm.attach(k::Chain::from_root(elbow.clone()), &wrist, mount) {
+            Err(k::Error::SharedNodeError { joint_name }) => {
+                assert_eq!(joint_name, "r_elbow_pitch")
+            }
+            _ => panic!("shared node must be an error"),
+        }
+        assert!(elbow.parent() == elbow_parent);
+        let other_arm = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        match arm.attach(other_arm, &wrist, mount) {
+            Err(k::Error::DuplicateJointNameError { joint_name }) => assert_eq!(joint_name, "root"),
+            _ => panic!("duplicated name must be an error"),
+        }
+        let tool = k::NodeBuilder::new().name("tool").into_node();
+        tool.set_link(Some(
+            k::link::LinkBuilder::new().name("l_wrist2").finalize(),
+        ));
+        match arm.attach(k::Chain::from_root(tool), &wrist, mount) {
+            Err(k::Error::DuplicateLinkNameError { link_name }) => {
+                assert_eq!(link_name, "l_wrist2")
+            }
+            _ => panic!("duplicated link name must be an error"),
+        }
+        assert_eq!(arm.iter().count(), num_nodes);
+        assert_eq!(
+            arm.find_link("l_wrist2").unwrap().joint().name,
+            "l_wrist_pitch"
+        );
+
+        // the root can not be detached, the chain is not changed
+        let root = arm.find("root").unwrap().clone();
+        match arm.detach(&root) {
+            Err(k::Error::DetachRootError { joint_name }) => assert_eq!(joint_name, "root"),
+            _ => panic!("detaching the root must be an error"),
+        }
+        assert_eq!(arm.iter().count(), num_nodes);
+        assert_eq!(arm.origin(), k::Isometry3::identity());
+
+        let gripper = create_gripper();
+        let gripper_root = gripper.iter().next().unwrap().clone();
+        let elbow_handle = arm.joint_handle("r_elbow_pitch").unwrap();
+        arm.attach(gripper, &wrist, mount).unwrap();
+        // the handles are kept by attach
+        assert_eq!(elbow_handle.position_index(), Some(3));
+        let finger_handle = arm.joint_handle("finger_l").unwrap();
+        assert_eq!(finger_handle.position_index(), Some(13));
+        assert_eq!(arm.iter().count(), num_nodes + 3);
+        assert_eq!(arm.dof(), 14);
+        assert_eq!(arm.num_positions(), 14);
+        // the current positions keep the order
+        assert_eq!(arm.joint_positions()[..12], positions[..]);
+        let transforms = arm.update_transforms();
+        let expected = wrist.world_transform().unwrap() * mount;
+        assert!(
+            (transforms[num_nodes].translation.vector - expected.translation.vector).norm() < 1e-10
+        );
+        let cloned = arm.clone();
+        assert_eq!(cloned.dof(), 14);
+        assert_eq!(cloned.joint_positions(), arm.joint_positions());
+
+        // the coupling over the detached node is removed
+        let finger_l = arm.find("finger_l").unwrap().clone();
+        finger_l.set_mimic_parent(&wrist, k::joint::Mimic::new(0.01, 0.0));
+        let detached = arm.detach(&gripper_root).unwrap();
+        assert!(gripper_root.parent().is_none());
+        // the handles are invalidated by detach
+        assert_eq!(elbow_handle.position_index(), None);
+        assert_eq!(finger_handle.position_index(), None);
+        assert_eq!(arm.iter().count(), num_nodes);
+        assert_eq!(arm.dof(), 12);
+        assert_eq!(detached.dof(), 2);
+        assert!(wrist.children().is_empty());
+        assert!(arm.find("r_wrist_pitch").unwrap() == &wrist);
+        assert!(finger_l.mimic_parent().is_none());
+        assert_eq!(detached.active_dof(), 1);
+        // the coupling in the detached chain is kept
+        let detached = detached.clone();
+        detached
+            .find("finger_l")
+            .unwrap()
+            .set_joint_position(0.02)
+            .unwrap();
+        assert_eq!(
+            detached.find("finger_r").unwrap().joint_position(),
+            Some(-0.02)
+        );
+        assert!(arm.detach(&gripper_root).is_err());
+    }
+
+    #[test]
+    fn test_rc_backend_same_as_default() {
+        use k::prelude::*;
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<k::Chain<f64>>();
+
+        let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        let rc_chain: k::RcChain<f64> = chain.to_backend();
+        assert_eq!(rc_chain.dof(), chain.dof());
+        let positions = vec![0.2; chain.dof()];
+        chain.set_joint_positions(&positions).unwrap();
+        rc_chain.set_joint_positions(&positions).unwrap();
+        assert_eq!(rc_chain.update_transforms(), chain.update_transforms());
+
+        let arm = k::SerialChain::from_end(chain.find("l_wrist_pitch").unwrap());
+        let rc_arm = k::SerialChain::from_end(rc_chain.find("l_wrist_pitch").unwrap());
+        assert_eq!(k::jacobian(&rc_arm), k::jacobian(&arm));
+        let arm_positions = [0.1, 0.2, 0.0, -0.5, 0.0, -0.3];
+        arm.set_joint_positions(&arm_positions).unwrap();
+        rc_arm.set_joint_positions(&arm_positions).unwrap();
+        let mut target = *arm.update_transforms().last().unwrap();
+        target.translation.vector.x -= 0.1;
+        let solver = k::JacobianIKSolver::default();
+        solver.solve(&arm, &target).unwrap();
+        solver.solve(&rc_arm, &target).unwrap();
+        assert_eq!(rc_arm.joint_positions(), arm.joint_positions());
+
+        // the nodes of the other backend are built by `From<Joint>`
+        let root: k::RcNode<f64> = k::NodeBuilder::new().name("root").finalize().into();
+        let node: k::RcNode<f64> = k::NodeBuilder::new()
+            .name("slider")
+            .joint_type(k::JointType::Linear {
+                axis: k::Vector3::x_axis(),
+            })
+            .finalize()
+            .into();
+        node.set_parent(&root);
+        let rc_chain = k::RcChain::from_root(root);
+        rc_chain.set_joint_positions(&[0.5]).unwrap();
+        let model = k::Model::from_chain(&rc_chain).unwrap();
+        assert_eq!(model.num_positions(), 1);
+        assert_eq!(rc_chain.clone().joint_positions(), vec![0.5]);
+    }
 }
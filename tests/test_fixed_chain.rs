@@ -0,0 +1,54 @@
+extern crate k;
+extern crate nalgebra as na;
+
+#[cfg(test)]
+mod tests {
+    use k::prelude::*;
+    use na::Vector6;
+    use std::convert::TryFrom;
+
+    fn create_arm() -> k::SerialChain<f64> {
+        let chain = k::Chain::<f64>::from_urdf_file("urdf/sample.urdf").unwrap();
+        k::SerialChain::from_end(chain.find("l_wrist_pitch").unwrap())
+    }
+
+    #[test]
+    fn fixed_jacobian_same_as_dynamic() {
+        let arm = k::FixedSerialChain::<f64, 6>::try_from(create_arm()).unwrap();
+        let positions = Vector6::new(0.5, 0.2, 0.0, -0.5, 0.0, -0.3);
+        arm.set_joint_positions(&positions).unwrap();
+        assert_eq!(arm.joint_positions(), positions);
+        let fixed = arm.jacobian();
+        let dynamic = k::jacobian(&arm);
+        assert_eq!(dynamic.shape(), (6, 6));
+        for r in 0..6 {
+            for c in 0..6 {
+                assert!((fixed[(r, c)] - dynamic[(r, c)]).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_ik() {
+        let arm = k::FixedSerialChain::<f64, 6>::try_new(create_arm()).unwrap();
+        arm.set_joint_positions(&Vector6::new(0.5, 0.2, 0.0, -0.5, 0.0, -0.3))
+            .unwrap();
+        let mut target = arm.end_transform();
+        target.translation.vector[0] += 0.02;
+        let solver = k::JacobianIKSolver::default();
+        solver.solve(&arm, &target).unwrap();
+        let reached = arm.end_transform();
+        assert!((reached.translation.vector - target.translation.vector).norm() < 1e-3);
+    }
+
+    #[test]
+    fn wrong_dof() {
+        match k::FixedSerialChain::<f64, 5>::try_new(create_arm()) {
+            Err(k::Error::SizeMismatchError { input, required }) => {
+                assert_eq!(input, 6);
+                assert_eq!(required, 5);
+            }
+            _ => panic!("the dof must be checked"),
+        }
+    }
+}
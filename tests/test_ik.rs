@@ -152,4 +152,195 @@ mod tests {
             assert!((init - end).abs() < 0.002);
         }
     }
+
+    #[test]
+    pub fn ik_continuous_joint_wraps() {
+        let l0: k::Node<f64> = k::NodeBuilder::new()
+            .name("wheel")
+            .joint_type(k::JointType::Continuous {
+                axis: Vector3::z_axis(),
+            })
+            .into_node();
+        let l1: k::Node<f64> = k::NodeBuilder::new()
+            .name("arm")
+            .joint_type(k::JointType::Linear {
+                axis: Vector3::x_axis(),
+            })
+            .translation(Translation3::new(0.5, 0.0, 0.0))
+            .into_node();
+        let l2: k::Node<f64> = k::NodeBuilder::new()
+            .name("hand")
+            .translation(Translation3::new(0.1, 0.0, 0.0))
+            .into_node();
+        connect![l0 => l1 => l2];
+        let arm = k::SerialChain::from_end(&l2);
+        // target is over +-pi from the initial angle
+        arm.set_joint_positions(&[3.2, 0.2]).unwrap();
+        let target = *arm.update_transforms().last().unwrap();
+        arm.set_joint_positions(&[3.0, 0.1]).unwrap();
+        let positions = arm.joint_positions();
+        assert!(positions[0] > -std::f64::consts::PI && positions[0] <= std::f64::consts::PI);
+        let solver = k::JacobianIKSolver::new(0.001, 0.001, 0.8, 100);
+        let mut constraints = k::Constraints::default();
+        constraints.position_z = false;
+        constraints.rotation_x = false;
+        constraints.rotation_y = false;
+        constraints.rotation_z = false;
+        solver
+            .solve_with_constraints(&arm, &target, &constraints)
+            .unwrap();
+        let positions = arm.joint_positions();
+        assert!((positions[0] - (3.2 - 2.0 * std::f64::consts::PI)).abs() < 0.01);
+        assert!((positions[1] - 0.2).abs() < 0.01);
+    }
+
+    #[test]
+    pub fn ik_spherical_joint() {
+        let l0: k::Node<f64> = k::NodeBuilder::new()
+            .name("ball")
+            .joint_type(k::JointType::Spherical)
+            .into_node();
+        let l1: k::Node<f64> = k::NodeBuilder::new()
+            .name("hand")
+            .translation(Translation3::new(0.0, 0.0, 0.3))
+            .into_node();
+        connect![l0 => l1];
+        let arm = k::SerialChain::from_end(&l1);
+        let rotation = na::UnitQuaternion::from_euler_angles(0.3, -0.2, 0.5);
+        let mut target = arm.end_transform();
+        target.rotation = rotation;
+        let solver = k::JacobianIKSolver::new(0.001, 0.001, 0.8, 100);
+        let mut constraints = k::Constraints::default();
+        constraints.position_x = false;
+        constraints.position_y = false;
+        constraints.position_z = false;
+        solver
+            .solve_with_constraints(&arm, &target, &constraints)
+            .unwrap();
+        assert!(arm.end_transform().rotation.angle_to(&rotation) < 0.01);
+        let positions = arm.joint_positions();
+        assert_eq!(positions.len(), 4);
+        assert!((na::Vector4::from_row_slice(&positions).norm() - 1.0).abs() < 1e-10);
+    }
+
+    #[test]
+    pub fn ik_coupled_joints() {
+        let new_node = |name: &str, x: f64| {
+            k::NodeBuilder::<f64>::new()
+                .name(name)
+                .joint_type(k::JointType::Rotational {
+                    axis: Vector3::z_axis(),
+                })
+                .translation(Translation3::new(x, 0.0, 0.0))
+                .into_node()
+        };
+        let l0 = new_node("j0", 0.0);
+        let l1 = new_node("j1", 0.5);
+        let l2 = new_node("j2", 0.4);
+        let l3: k::Node<f64> = k::NodeBuilder::new()
+            .name("hand")
+            .translation(Translation3::new(0.3, 0.0, 0.0))
+            .into_node();
+        connect![l0 => l1 => l2 => l3];
+        // nonlinear coupling like a linkage
+        l2.set_coupling(
+            &[&l1],
+            k::joint::FnCoupling::new(|q: &[f64]| 0.5 * q[0] * q[0] + 0.2 * q[0]),
+        );
+        let arm = k::SerialChain::from_end(&l3);
+        // only j0 and j1 are solved
+        assert_eq!(arm.active_dof(), 2);
+        arm.set_joint_positions(&[0.4, 0.6, 0.0]).unwrap();
+        let target = arm.end_transform();
+        arm.set_joint_positions(&[0.2, 0.3, 0.0]).unwrap();
+        let solver = k::JacobianIKSolver::new(0.0001, 0.001, 0.5, 100);
+        let mut constraints = k::Constraints::default();
+        constraints.position_z = false;
+        constraints.rotation_x = false;
+        constraints.rotation_y = false;
+        constraints.rotation_z = false;
+        solver
+            .solve_with_constraints(&arm, &target, &constraints)
+            .unwrap();
+        let end = arm.end_transform();
+        assert!((end.translation.vector - target.translation.vector).norm() < 0.001);
+        // the coupling is kept
+        let positions = arm.joint_positions();
+        let expected = 0.5 * positions[1] * positions[1] + 0.2 * positions[1];
+        assert!((positions[2] - expected).abs() < 1e-10);
+    }
+
+    #[test]
+    pub fn ik_soft_limits() {
+        let create_arm = |safety_controller: Option<k::joint::SafetyController<f64>>| {
+            let l0 = k::NodeBuilder::<f64>::new()
+                .name("j0")
+                .joint_type(k::JointType::Rotational {
+                    axis: Vector3::z_axis(),
+                })
+                .into_node();
+            let l1 = k::NodeBuilder::new()
+                .name("j1")
+                .joint_type(k::JointType::Rotational {
+                    axis: Vector3::z_axis(),
+                })
+                .translation(Translation3::new(0.5, 0.0, 0.0))
+                .limits(Some((-2.0..=2.0).into()))
+                .safety_controller(safety_controller)
+                .into_node();
+            let l2 = k::NodeBuilder::new()
+                .name("hand")
+                .translation(Translation3::new(0.4, 0.0, 0.0))
+                .into_node();
+            connect![l0 => l1 => l2];
+            k::SerialChain::from_end(&l2)
+        };
+        let soft_arm = create_arm(Some(k::joint::SafetyController::new(
+            (-0.3..=0.3).into(),
+            10.0,
+            1.0,
+        )));
+        let arm = create_arm(None);
+        // the hard limits allow this
+        soft_arm.set_joint_positions(&[0.2, 1.0]).unwrap();
+        let target = soft_arm.end_transform();
+        soft_arm.set_joint_positions(&[0.0, 0.1]).unwrap();
+        arm.set_joint_positions(&[0.0, 0.1]).unwrap();
+        let solver = k::JacobianIKSolver::new(0.0001, 0.001, 0.5, 100);
+        let mut constraints = k::Constraints::default();
+        constraints.position_z = false;
+        constraints.rotation_x = false;
+        constraints.rotation_y = false;
+        constraints.rotation_z = false;
+        // not reachable in the soft limits
+        assert!(solver
+            .solve_with_constraints(&soft_arm, &target, &constraints)
+            .is_err());
+        assert_eq!(soft_arm.joint_positions(), vec![0.0, 0.1]);
+
+        solver
+            .solve_with_constraints(&arm, &target, &constraints)
+            .unwrap();
+        assert!(arm.joint_positions()[1].abs() > 0.3);
+    }
+
+    #[test]
+    pub fn soft_limits_out_of_hard_limits() {
+        let node = k::NodeBuilder::<f64>::new()
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::z_axis(),
+            })
+            .limits(Some((-1.0..=1.0).into()))
+            .safety_controller(Some(k::joint::SafetyController::new(
+                (1.5..=2.0).into(),
+                10.0,
+                1.0,
+            )))
+            .into_node();
+        // clamped to the nearest hard limit, not to the whole hard range
+        node.set_joint_position_clamped(-0.5);
+        assert_eq!(node.joint_position(), Some(1.0));
+        node.set_joint_position_clamped(3.0);
+        assert_eq!(node.joint_position(), Some(1.0));
+    }
 }
@@ -0,0 +1,222 @@
+extern crate k;
+extern crate nalgebra as na;
+
+#[cfg(test)]
+mod tests {
+    use na::{DVector, Isometry3, Translation3, UnitQuaternion, Vector3};
+
+    fn create_arm(joint_types: Vec<k::JointType<f64>>) -> k::SerialChain<f64> {
+        let nodes = joint_types
+            .into_iter()
+            .enumerate()
+            .map(|(i, joint_type)| {
+                k::NodeBuilder::new()
+                    .name(&format!("j{}", i))
+                    .translation(Translation3::new(0.1, -0.2, 0.3))
+                    .rotation(UnitQuaternion::from_euler_angles(0.2, -0.1, 0.4))
+                    .joint_type(joint_type)
+                    .into_node()
+            })
+            .collect::<Vec<_>>();
+        let end = k::NodeBuilder::new()
+            .name("end")
+            .translation(Translation3::new(0.0, 0.1, 0.2))
+            .into_node();
+        for pair in nodes.windows(2) {
+            pair[1].set_parent(&pair[0]);
+        }
+        end.set_parent(nodes.last().unwrap());
+        k::SerialChain::from_end(&end)
+    }
+
+    fn end_transform_at(arm: &k::SerialChain<f64>, positions: &[f64]) -> Isometry3<f64> {
+        arm.set_joint_positions(positions).unwrap();
+        arm.end_transform()
+    }
+
+    /// Check the jacobian and the velocities with the finite difference
+    fn check_jacobian(arm: &k::SerialChain<f64>, positions: &[f64]) {
+        let dof = arm.dof();
+        arm.set_joint_positions(positions).unwrap();
+        let positions = arm.joint_positions();
+        let jacobian = k::jacobian(arm);
+        assert_eq!(jacobian.shape(), (6, dof));
+        let dt = 1e-6;
+        for i in 0..dof {
+            let mut velocities = vec![0.0; dof];
+            velocities[i] = dt;
+            let plus = end_transform_at(
+                arm,
+                &arm.integrate_joint_positions(&positions, &velocities)
+                    .unwrap(),
+            );
+            velocities[i] = -dt;
+            let minus = end_transform_at(
+                arm,
+                &arm.integrate_joint_positions(&positions, &velocities)
+                    .unwrap(),
+            );
+            let linear = (plus.translation.vector - minus.translation.vector) / (2.0 * dt);
+            let angular = (plus.rotation * minus.rotation.inverse()).scaled_axis() / (2.0 * dt);
+            for r in 0..3 {
+                assert!((jacobian[(r, i)] - linear[r]).abs() < 1e-6);
+                assert!((jacobian[(r + 3, i)] - angular[r]).abs() < 1e-6);
+            }
+        }
+        arm.set_joint_positions(&positions).unwrap();
+        let velocities = (0..dof).map(|i| 0.1 * i as f64 - 0.3).collect::<Vec<_>>();
+        arm.set_joint_velocities(&velocities).unwrap();
+        let end_velocity = arm.end_velocity();
+        let by_jacobian = jacobian * DVector::from_vec(velocities);
+        for r in 0..3 {
+            assert!((end_velocity.translation[r] - by_jacobian[r]).abs() < 1e-10);
+            assert!((end_velocity.rotation[r] - by_jacobian[r + 3]).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn planar_and_floating_jacobian() {
+        let arm = create_arm(vec![
+            k::JointType::Floating,
+            k::JointType::Planar {
+                normal: Vector3::y_axis(),
+            },
+            k::JointType::Rotational {
+                axis: Vector3::x_axis(),
+            },
+        ]);
+        assert_eq!(arm.dof(), 10);
+        assert_eq!(arm.num_positions(), 11);
+        let rotation = UnitQuaternion::from_euler_angles(0.3, -0.6, 1.2);
+        let mut positions = vec![0.4, -0.2, 0.7];
+        positions.extend_from_slice(rotation.coords.as_slice());
+        positions.extend_from_slice(&[0.3, -0.5, 2.0, -0.8]);
+        check_jacobian(&arm, &positions);
+    }
+
+    #[test]
+    fn floating_transform() {
+        let arm = create_arm(vec![k::JointType::Floating]);
+        let rotation = UnitQuaternion::from_euler_angles(0.3, -0.6, 1.2);
+        let mut positions = vec![1.0, 2.0, 3.0];
+        positions.extend_from_slice(rotation.coords.as_slice());
+        arm.set_joint_positions(&positions).unwrap();
+        let node = arm.find("j0").unwrap();
+        let expected = Isometry3::from_parts(
+            Translation3::new(0.1, -0.2, 0.3),
+            UnitQuaternion::from_euler_angles(0.2, -0.1, 0.4),
+        ) * Isometry3::from_parts(Translation3::new(1.0, 2.0, 3.0), rotation);
+        let trans = node.joint().local_transform();
+        assert!((trans.translation.vector - expected.translation.vector).norm() < 1e-10);
+        assert!(trans.rotation.angle_to(&expected.rotation) < 1e-10);
+        // wrong size
+        assert!(arm.set_joint_positions(&[0.0; 6]).is_err());
+    }
+
+    #[test]
+    fn interpolate_planar() {
+        let arm = create_arm(vec![k::JointType::Planar {
+            normal: Vector3::z_axis(),
+        }]);
+        let middle = arm
+            .interpolate_joint_positions(&[0.0, 0.0, 3.0], &[1.0, 2.0, -3.0], 0.5)
+            .unwrap();
+        assert!((middle[0] - 0.5).abs() < 1e-10);
+        assert!((middle[1] - 1.0).abs() < 1e-10);
+        // shortest path of the angle
+        assert!((middle[2].abs() - std::f64::consts::PI).abs() < 1e-10);
+    }
+
+    #[test]
+    fn spherical_and_universal_jacobian() {
+        let arm = create_arm(vec![
+            k::JointType::Universal {
+                axis1: Vector3::z_axis(),
+                axis2: Vector3::y_axis(),
+            },
+            k::JointType::Linear {
+                axis: Vector3::x_axis(),
+            },
+            k::JointType::Spherical,
+        ]);
+        assert_eq!(arm.dof(), 6);
+        assert_eq!(arm.num_positions(), 7);
+        let mut positions = vec![0.4, -0.7, 0.3];
+        positions.extend_from_slice(
+            UnitQuaternion::from_euler_angles(-0.4, 0.8, 0.1)
+                .coords
+                .as_slice(),
+        );
+        check_jacobian(&arm, &positions);
+    }
+
+    #[test]
+    fn universal_and_spherical_acceleration() {
+        let arm = create_arm(vec![
+            k::JointType::Universal {
+                axis1: Vector3::x_axis(),
+                axis2: Vector3::z_axis(),
+            },
+            k::JointType::Spherical,
+        ]);
+        let mut positions = vec![0.5, -0.3];
+        positions.extend_from_slice(
+            UnitQuaternion::from_euler_angles(0.2, 0.1, -0.6)
+                .coords
+                .as_slice(),
+        );
+        let velocities = [0.7, -1.1, 0.4, 0.9, -0.5];
+        let accelerations = [0.3, 0.6, -0.8, 0.2, 1.0];
+        let dt = 1e-6;
+        let velocities_at = |sign: f64| {
+            let delta = velocities
+                .iter()
+                .zip(accelerations.iter())
+                .map(|(v, a)| v * dt * sign + 0.5 * a * dt * dt)
+                .collect::<Vec<_>>();
+            arm.set_joint_positions(&arm.integrate_joint_positions(&positions, &delta).unwrap())
+                .unwrap();
+            let next_velocities = velocities
+                .iter()
+                .zip(accelerations.iter())
+                .map(|(v, a)| v + a * dt * sign)
+                .collect::<Vec<_>>();
+            arm.set_joint_velocities(&next_velocities).unwrap();
+            arm.update_velocities()
+        };
+        let plus = velocities_at(1.0);
+        let minus = velocities_at(-1.0);
+
+        arm.set_joint_positions(&positions).unwrap();
+        arm.set_joint_velocities(&velocities).unwrap();
+        arm.set_joint_accelerations(&accelerations).unwrap();
+        let result = arm.update_accelerations();
+        for ((a, p), m) in result.iter().zip(plus.iter()).zip(minus.iter()) {
+            let linear = (p.translation - m.translation) / (2.0 * dt);
+            let angular = (p.rotation - m.rotation) / (2.0 * dt);
+            assert!((a.linear - linear).norm() < 1e-5);
+            assert!((a.angular - angular).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn helical_jacobian() {
+        let arm = create_arm(vec![
+            k::JointType::Helical {
+                axis: Vector3::z_axis(),
+                pitch: 0.05,
+            },
+            k::JointType::Helical {
+                axis: Vector3::x_axis(),
+                pitch: -0.2,
+            },
+        ]);
+        assert_eq!(arm.dof(), 2);
+        check_jacobian(&arm, &[0.8, -1.5]);
+        // the translation along the axis is pitch * angle
+        arm.set_joint_positions(&[2.0, 0.0]).unwrap();
+        let joint = arm.find("j0").unwrap().joint();
+        let translation = joint.origin().inverse() * joint.local_transform();
+        assert!((translation.translation.vector - Vector3::new(0.0, 0.0, 0.1)).norm() < 1e-10);
+    }
+}
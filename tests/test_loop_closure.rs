@@ -0,0 +1,147 @@
+extern crate k;
+extern crate nalgebra as na;
+
+#[cfg(test)]
+mod tests {
+    use na::{Isometry3, Translation3, Vector3};
+
+    fn rotational(name: &str, x: f64) -> k::Node<f64> {
+        k::NodeBuilder::new()
+            .name(name)
+            .translation(Translation3::new(x, 0.0, 0.0))
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::z_axis(),
+            })
+            .into_node()
+    }
+
+    /// Four-bar linkage, the crank is active and the others are passive
+    fn create_four_bar() -> k::Chain<f64> {
+        let root = k::NodeBuilder::new().name("root").into_node();
+        let crank = rotational("crank", 0.0);
+        let coupler = rotational("coupler", 1.0);
+        let rocker = rotational("rocker", 2.0);
+        let coupler_middle = k::NodeBuilder::new()
+            .name("coupler_middle")
+            .translation(Translation3::new(1.0, 0.0, 0.0))
+            .into_node();
+        crank.set_parent(&root);
+        coupler.set_parent(&crank);
+        coupler_middle.set_parent(&coupler);
+        rocker.set_parent(&root);
+        let mut chain = k::Chain::from_root(root);
+        chain.set_passive_nodes(&[&coupler, &rocker]);
+        chain.add_loop_closure(k::LoopClosure::new_point(
+            &coupler,
+            Vector3::new(2.0, 0.0, 0.0),
+            &rocker,
+            Vector3::new(1.5, 0.0, 0.0),
+        ));
+        // initial guess of the assembly mode
+        coupler.set_joint_position(0.0).unwrap();
+        rocker.set_joint_position(1.5).unwrap();
+        chain
+    }
+
+    fn closure_error(chain: &k::Chain<f64>) -> f64 {
+        chain.update_transforms();
+        chain
+            .loop_closures()
+            .iter()
+            .map(|closure| closure.error().norm())
+            .sum()
+    }
+
+    #[test]
+    fn four_bar_forward_kinematics() {
+        let chain = create_four_bar();
+        assert_eq!(chain.dof(), 3);
+        assert_eq!(chain.active_dof(), 1);
+        let solver = k::LoopClosureSolver::default();
+        let mut last_rocker: Option<f64> = None;
+        for i in 0..10 {
+            let crank = 0.5 + 0.1 * i as f64;
+            solver.set_active_joint_positions(&chain, &[crank]).unwrap();
+            assert!(closure_error(&chain) < 1e-6);
+            assert!((chain.find("crank").unwrap().joint_position().unwrap() - crank).abs() < 1e-10);
+            // the rocker moves continuously
+            let rocker = chain.find("rocker").unwrap().joint_position().unwrap();
+            if let Some(last) = last_rocker {
+                assert!((rocker - last).abs() < 0.2);
+            }
+            last_rocker = Some(rocker);
+        }
+    }
+
+    #[test]
+    fn four_bar_inverse_kinematics() {
+        let chain = create_four_bar();
+        let solver = k::LoopClosureSolver::default();
+        solver.set_active_joint_positions(&chain, &[1.0]).unwrap();
+        let end = chain.find("coupler_middle").unwrap();
+        let target = end.world_transform().unwrap();
+
+        solver.set_active_joint_positions(&chain, &[0.6]).unwrap();
+        let mut constraints = k::Constraints::default();
+        constraints.position_z = false;
+        constraints.rotation_x = false;
+        constraints.rotation_y = false;
+        constraints.rotation_z = false;
+        solver.solve_ik(&chain, end, &target, &constraints).unwrap();
+        chain.update_transforms();
+        let reached = end.world_transform().unwrap();
+        assert!((reached.translation.vector - target.translation.vector).norm() < 1e-6);
+        assert!(closure_error(&chain) < 1e-6);
+        assert!((chain.active_joint_positions()[0] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pose_closure_and_clone() {
+        let root = k::NodeBuilder::new().name("root").into_node();
+        let active = rotational("active", 0.0);
+        let passive = rotational("passive", 0.0);
+        active.set_parent(&root);
+        passive.set_parent(&root);
+        let mut chain = k::Chain::from_root(root);
+        chain.set_passive_nodes(&[&passive]);
+        let frame = Isometry3::translation(1.0, 0.0, 0.0);
+        chain.add_loop_closure(k::LoopClosure::new_pose(&active, frame, &passive, frame));
+
+        let cloned = chain.clone();
+        assert_eq!(cloned.passive_nodes().len(), 1);
+        assert_eq!(cloned.passive_nodes()[0].joint().name, "passive");
+        assert_eq!(cloned.loop_closures()[0].closure_type, k::ClosureType::Pose);
+        let solver = k::LoopClosureSolver::default();
+        solver.set_active_joint_positions(&cloned, &[0.4]).unwrap();
+        let positions = cloned.joint_positions();
+        assert!((positions[1] - 0.4).abs() < 1e-6);
+        // the original chain is not changed
+        assert_eq!(chain.joint_positions(), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn four_bar_restore_on_failure() {
+        let chain = create_four_bar();
+        k::LoopClosureSolver::default()
+            .set_active_joint_positions(&chain, &[0.5])
+            .unwrap();
+        let positions = chain.joint_positions();
+        // no iteration is allowed, so the loop can not be closed
+        let solver = k::LoopClosureSolver::new(1e-8, 0);
+        assert!(solver.set_active_joint_positions(&chain, &[0.8]).is_err());
+        assert_eq!(chain.joint_positions(), positions);
+    }
+
+    #[test]
+    fn closure_error_without_cache() {
+        let chain = create_four_bar();
+        chain
+            .find("crank")
+            .unwrap()
+            .set_joint_position(0.3)
+            .unwrap();
+        // the world transforms are calculated from the joint positions
+        let error = chain.loop_closures()[0].error();
+        assert!((error.norm() - closure_error(&chain)).abs() < 1e-10);
+    }
+}
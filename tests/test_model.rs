@@ -0,0 +1,206 @@
+extern crate k;
+extern crate nalgebra as na;
+
+#[cfg(test)]
+mod tests {
+    use na::{Translation3, UnitQuaternion, Vector3};
+    use std::sync::Arc;
+
+    fn create_tree() -> k::Chain<f64> {
+        let base = k::NodeBuilder::new()
+            .name("base")
+            .joint_type(k::JointType::Floating)
+            .into_node();
+        let left = k::NodeBuilder::new()
+            .name("left")
+            .translation(Translation3::new(0.0, 0.2, 0.1))
+            .rotation(UnitQuaternion::from_euler_angles(0.1, 0.2, -0.3))
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::z_axis(),
+            })
+            .limits(Some(k::joint::Range::new(-1.0, 1.0)))
+            .into_node();
+        let right = k::NodeBuilder::new()
+            .name("right")
+            .translation(Translation3::new(0.0, -0.2, 0.1))
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::z_axis(),
+            })
+            .into_node();
+        let slider = k::NodeBuilder::new()
+            .name("slider")
+            .translation(Translation3::new(0.3, 0.0, 0.0))
+            .joint_type(k::JointType::Linear {
+                axis: Vector3::x_axis(),
+            })
+            .into_node();
+        let end = k::NodeBuilder::new()
+            .name("end")
+            .translation(Translation3::new(0.1, 0.0, 0.0))
+            .into_node();
+        left.set_parent(&base);
+        right.set_parent(&base);
+        slider.set_parent(&left);
+        end.set_parent(&slider);
+        right.set_mimic_parent(&left, k::joint::Mimic::new(-1.0, 0.1));
+        k::Chain::from_root(base)
+    }
+
+    fn positions(t: f64) -> Vec<f64> {
+        let mut positions = vec![0.1 * t, -0.2, 0.3];
+        positions.extend_from_slice(
+            UnitQuaternion::from_euler_angles(0.2 * t, -0.1, 0.4)
+                .coords
+                .as_slice(),
+        );
+        positions.extend_from_slice(&[0.5 * t.sin(), 0.0, 0.1 * t]);
+        positions
+    }
+
+    #[test]
+    fn forward_kinematics_same_as_chain() {
+        let chain = create_tree();
+        let model = k::Model::from_chain(&chain).unwrap();
+        assert_eq!(model.len(), 5);
+        assert_eq!(model.num_positions(), chain.num_positions());
+        assert_eq!(model.dof(), chain.dof());
+        assert_eq!(model.parent(0), None);
+        assert_eq!(
+            model.parent(model.find("end").unwrap()),
+            model.find("slider")
+        );
+
+        let mut state = model.create_state();
+        for i in 0..5 {
+            let positions = positions(i as f64);
+            chain.set_joint_positions(&positions).unwrap();
+            model.set_positions(&mut state, &positions).unwrap();
+            assert_eq!(state.positions(), chain.joint_positions().as_slice());
+            let transforms = chain.update_transforms();
+            model.forward_kinematics(&mut state);
+            for (expected, actual) in transforms.iter().zip(state.transforms()) {
+                assert!((expected.translation.vector - actual.translation.vector).norm() < 1e-10);
+                assert!(expected.rotation.angle_to(&actual.rotation) < 1e-10);
+            }
+        }
+        // the mimic joint follows the parent
+        let left = model.find("left").unwrap();
+        let right = model.find("right").unwrap();
+        let left_position = model.joint_positions(&state, left)[0];
+        assert!((model.joint_positions(&state, right)[0] + left_position - 0.1).abs() < 1e-10);
+
+        let mut out_of_limit = positions(0.0);
+        out_of_limit[model.position_offset(left)] = 2.0;
+        assert!(model.set_positions(&mut state, &out_of_limit).is_err());
+        assert!(model.set_positions(&mut state, &[0.0; 3]).is_err());
+    }
+
+    #[test]
+    fn share_model_between_threads() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<k::Model<f64>>();
+
+        let chain = create_tree();
+        let model = Arc::new(k::Model::from_chain(&chain).unwrap());
+        let end = model.find("end").unwrap();
+        let handles = (0..4)
+            .map(|i| {
+                let model = model.clone();
+                std::thread::spawn(move || {
+                    let mut state = model.create_state();
+                    model
+                        .set_positions(&mut state, &positions(i as f64))
+                        .unwrap();
+                    model.forward_kinematics(&mut state);
+                    *state.transform(end)
+                })
+            })
+            .collect::<Vec<_>>();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let transform = handle.join().unwrap();
+            chain.set_joint_positions(&positions(i as f64)).unwrap();
+            chain.update_transforms();
+            let expected = chain.find("end").unwrap().world_transform().unwrap();
+            assert!((expected.translation.vector - transform.translation.vector).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn batch_same_as_state() {
+        let model = k::Model::from_chain(&create_tree()).unwrap();
+        let num_samples = 5;
+        let mut batch_positions = vec![0.0; model.num_positions() * num_samples];
+        for s in 0..num_samples {
+            for (i, position) in positions(s as f64).into_iter().enumerate() {
+                batch_positions[i * num_samples + s] = position;
+            }
+        }
+        let mut state = model.create_state();
+        for name in &["end", "right"] {
+            let index = model.find(name).unwrap();
+            let poses = model
+                .batch_end_poses(index, &batch_positions, num_samples)
+                .unwrap();
+            assert_eq!(poses.len(), num_samples);
+            for s in 0..num_samples {
+                model
+                    .set_positions(&mut state, &positions(s as f64))
+                    .unwrap();
+                model.forward_kinematics(&mut state);
+                let expected = state.transform(index);
+                let pose = poses.pose(s);
+                assert!((expected.translation.vector - pose.translation.vector).norm() < 1e-10);
+                assert!(expected.rotation.angle_to(&pose.rotation) < 1e-10);
+            }
+        }
+        assert!(model.batch_end_poses(0, &batch_positions, 4).is_err());
+        assert!(model
+            .batch_end_poses(model.len(), &batch_positions, num_samples)
+            .is_err());
+    }
+
+    #[test]
+    fn jacobian_same_as_serial_chain() {
+        let chain = create_tree();
+        let model = k::Model::from_chain(&chain).unwrap();
+        let mut state = model.create_state();
+        let positions = positions(2.0);
+        chain.set_joint_positions(&positions).unwrap();
+        model.set_positions(&mut state, &positions).unwrap();
+        model.forward_kinematics(&mut state);
+        for name in &["end", "right"] {
+            let arm = k::SerialChain::from_end(chain.find(name).unwrap());
+            let expected = k::jacobian(&arm);
+            let jacobi = model.jacobian(&state, model.find(name).unwrap());
+            assert_eq!(jacobi.shape(), (6, model.dof()));
+            // the columns of the joints in the arm
+            let mut col = 0;
+            for node in arm.iter() {
+                let index = model.find(&node.joint().name).unwrap();
+                if !node.is_mimic() && node.joint().is_movable() {
+                    let offset = model.velocity_offset(index);
+                    for i in 0..model.joint_type(index).dof() {
+                        let diff = jacobi.column(offset + i) - expected.column(col + i);
+                        assert!(diff.norm() < 1e-10);
+                    }
+                    col += model.joint_type(index).dof();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn circular_coupling_is_error() {
+        let chain = create_tree();
+        let left = chain.find("left").unwrap();
+        let slider = chain.find("slider").unwrap();
+        left.set_mimic_parent(slider, k::joint::Mimic::new(1.0, 0.0));
+        slider.set_mimic_parent(left, k::joint::Mimic::new(1.0, 0.0));
+        match k::Model::from_chain(&chain) {
+            Err(k::Error::CircularCouplingError { joint_name }) => {
+                assert!(joint_name == "left" || joint_name == "slider")
+            }
+            _ => panic!("circular coupling must be an error"),
+        }
+    }
+}
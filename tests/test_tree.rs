@@ -0,0 +1,113 @@
+extern crate k;
+extern crate nalgebra as na;
+
+#[cfg(test)]
+mod tests {
+    /// `urdf/sample.urdf` whose `r_shoulder_yaw` mimics `l_shoulder_yaw`
+    fn create_chain() -> k::Chain<f64> {
+        let chain = k::Chain::from_urdf_file("urdf/sample.urdf").unwrap();
+        chain.find("r_shoulder_yaw").unwrap().set_mimic_parent(
+            chain.find("l_shoulder_yaw").unwrap(),
+            k::joint::Mimic::new(-1.0, 0.1),
+        );
+        chain
+    }
+
+    #[test]
+    fn convert_from_and_to_chain() {
+        let chain = create_chain();
+        chain
+            .find("l_shoulder_yaw")
+            .unwrap()
+            .set_joint_position(0.3)
+            .unwrap();
+        chain
+            .find("l_elbow_pitch")
+            .unwrap()
+            .set_joint_position(-0.5)
+            .unwrap();
+        let tree = k::Tree::from_chain(&chain).unwrap();
+        assert_eq!(tree.len(), 13);
+        assert_eq!(
+            tree.roots().collect::<Vec<_>>(),
+            vec![tree.find("root").unwrap()]
+        );
+        let l_shoulder_yaw = tree.find("l_shoulder_yaw").unwrap();
+        assert_eq!(
+            tree.parent(tree.find("l_shoulder_pitch").unwrap()),
+            Some(l_shoulder_yaw)
+        );
+        assert_eq!(
+            tree.mimic_parents(tree.find("r_shoulder_yaw").unwrap()),
+            &[l_shoulder_yaw]
+        );
+
+        let transforms = tree.update_transforms();
+        let chain_transforms = chain.update_transforms();
+        assert_eq!(transforms.len(), chain_transforms.len());
+        for (a, b) in transforms.iter().zip(&chain_transforms) {
+            assert!((a.translation.vector - b.translation.vector).norm() < 1e-10);
+            assert!(a.rotation.angle_to(&b.rotation) < 1e-10);
+        }
+
+        let converted = tree.to_chain();
+        assert_eq!(converted.joint_positions(), chain.joint_positions());
+        converted
+            .find("l_shoulder_yaw")
+            .unwrap()
+            .set_joint_position(0.5)
+            .unwrap();
+        let r_shoulder_yaw = converted.find("r_shoulder_yaw").unwrap();
+        assert!((r_shoulder_yaw.joint_position().unwrap() + 0.4).abs() < 1e-10);
+        // the original nodes are not shared
+        assert_eq!(
+            chain.find("l_shoulder_yaw").unwrap().joint_position(),
+            Some(0.3)
+        );
+    }
+
+    #[test]
+    fn clone_and_update_mimic() {
+        let tree = k::Tree::from_chain(&create_chain()).unwrap();
+        let mut cloned = tree.clone();
+        let left = cloned.find("l_shoulder_yaw").unwrap();
+        let right = cloned.find("r_shoulder_yaw").unwrap();
+        cloned.set_joint_position(left, 0.5).unwrap();
+        assert!((cloned.joint(right).joint_position().unwrap() + 0.4).abs() < 1e-10);
+        assert_eq!(tree.joint(left).joint_position(), Some(0.0));
+        // positions of the mimic joints can not be set directly
+        cloned.set_joint_position(right, 1.0).unwrap();
+        assert!((cloned.joint(right).joint_position().unwrap() + 0.4).abs() < 1e-10);
+    }
+
+    #[test]
+    fn reject_circular_coupling() {
+        let mut tree = k::Tree::from_chain(&create_chain()).unwrap();
+        let left = tree.find("l_shoulder_yaw").unwrap();
+        let right = tree.find("r_shoulder_yaw").unwrap();
+        let elbow = tree.find("l_elbow_pitch").unwrap();
+        assert!(tree
+            .set_coupling(left, &[right], k::joint::Mimic::new(1.0, 0.0))
+            .is_err());
+        assert!(tree
+            .set_coupling(left, &[left], k::joint::Mimic::new(1.0, 0.0))
+            .is_err());
+        tree.set_coupling(elbow, &[right], k::joint::Mimic::new(2.0, 0.0))
+            .unwrap();
+        assert!(tree
+            .set_coupling(left, &[elbow], k::joint::Mimic::new(1.0, 0.0))
+            .is_err());
+        // the rejected couplings are not set
+        assert!(tree.mimic_parents(left).is_empty());
+        tree.set_joint_position(left, 0.5).unwrap();
+        assert!((tree.joint(elbow).joint_position().unwrap() + 0.8).abs() < 1e-10);
+
+        // the circle made by the nodes is rejected by the conversion
+        let chain = create_chain();
+        chain.find("l_shoulder_yaw").unwrap().set_mimic_parent(
+            chain.find("r_shoulder_yaw").unwrap(),
+            k::joint::Mimic::new(1.0, 0.0),
+        );
+        assert!(k::Tree::from_chain(&chain).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+extern crate k;
+extern crate nalgebra as na;
+
+#[cfg(test)]
+mod tests {
+    use na::{Isometry3, Translation3, UnitQuaternion, Vector3};
+
+    fn create_tree() -> k::Chain<f64> {
+        let l0: k::Node<f64> = k::NodeBuilder::new()
+            .name("rot0")
+            .translation(Translation3::new(0.0, 0.0, 0.1))
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::y_axis(),
+            })
+            .into_node();
+        let l1: k::Node<f64> = k::NodeBuilder::new()
+            .name("lin1")
+            .translation(Translation3::new(0.1, 0.2, 0.3))
+            .rotation(UnitQuaternion::from_euler_angles(0.3, -0.2, 0.1))
+            .joint_type(k::JointType::Linear {
+                axis: Vector3::x_axis(),
+            })
+            .into_node();
+        let l2: k::Node<f64> = k::NodeBuilder::new()
+            .name("rot2")
+            .translation(Translation3::new(0.0, -0.1, 0.2))
+            .rotation(UnitQuaternion::from_euler_angles(-0.5, 0.0, 0.4))
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::z_axis(),
+            })
+            .into_node();
+        let l3: k::Node<f64> = k::NodeBuilder::new()
+            .name("fixed3")
+            .translation(Translation3::new(0.2, 0.0, 0.1))
+            .into_node();
+        let l4: k::Node<f64> = k::NodeBuilder::new()
+            .name("lin4")
+            .translation(Translation3::new(0.0, 0.3, 0.0))
+            .joint_type(k::JointType::Linear {
+                axis: Vector3::z_axis(),
+            })
+            .into_node();
+        let l5: k::Node<f64> = k::NodeBuilder::new()
+            .name("rot5")
+            .translation(Translation3::new(0.1, 0.0, -0.2))
+            .joint_type(k::JointType::Rotational {
+                axis: Vector3::x_axis(),
+            })
+            .into_node();
+        l1.set_parent(&l0);
+        l2.set_parent(&l1);
+        l3.set_parent(&l2);
+        l4.set_parent(&l0);
+        l5.set_parent(&l4);
+        k::Chain::from_root(l0)
+    }
+
+    fn transforms_at(chain: &k::Chain<f64>, positions: &[f64]) -> Vec<Isometry3<f64>> {
+        chain.set_joint_positions(positions).unwrap();
+        chain.update_transforms()
+    }
+
+    fn add(a: &[f64], b: &[f64], scale: f64) -> Vec<f64> {
+        a.iter().zip(b.iter()).map(|(x, y)| x + y * scale).collect()
+    }
+
+    const Q: [f64; 5] = [0.3, 0.2, -0.7, 0.1, 1.2];
+    const DQ: [f64; 5] = [0.5, -0.3, 1.1, 0.4, -0.8];
+    const DDQ: [f64; 5] = [-0.2, 0.7, 0.3, -1.0, 0.6];
+
+    #[test]
+    fn velocity_matches_finite_difference() {
+        let chain = create_tree();
+        let dt = 1e-6;
+        let plus = transforms_at(&chain, &add(&Q, &DQ, dt));
+        let minus = transforms_at(&chain, &add(&Q, &DQ, -dt));
+
+        chain.set_joint_positions(&Q).unwrap();
+        chain.set_joint_velocities(&DQ).unwrap();
+        let velocities = chain.update_velocities();
+        assert_eq!(velocities.len(), 6);
+        for ((v, p), m) in velocities.iter().zip(plus.iter()).zip(minus.iter()) {
+            let linear = (p.translation.vector - m.translation.vector) / (2.0 * dt);
+            let angular = (p.rotation * m.rotation.inverse()).scaled_axis() / (2.0 * dt);
+            assert!((v.translation - linear).norm() < 1e-6);
+            assert!((v.rotation - angular).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn acceleration_matches_finite_difference() {
+        let chain = create_tree();
+        let dt = 1e-6;
+        let velocities_at = |sign: f64| {
+            let positions = add(&add(&Q, &DQ, sign * dt), &DDQ, 0.5 * dt * dt);
+            chain.set_joint_positions(&positions).unwrap();
+            chain
+                .set_joint_velocities(&add(&DQ, &DDQ, sign * dt))
+                .unwrap();
+            chain.update_velocities()
+        };
+        let plus = velocities_at(1.0);
+        let minus = velocities_at(-1.0);
+
+        chain.set_joint_positions(&Q).unwrap();
+        chain.set_joint_velocities(&DQ).unwrap();
+        chain.set_joint_accelerations(&DDQ).unwrap();
+        let accelerations = chain.update_accelerations();
+        for ((a, p), m) in accelerations.iter().zip(plus.iter()).zip(minus.iter()) {
+            let linear = (p.translation - m.translation) / (2.0 * dt);
+            let angular = (p.rotation - m.rotation) / (2.0 * dt);
+            assert!((a.linear - linear).norm() < 1e-5);
+            assert!((a.angular - angular).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn body_and_point_velocity() {
+        let chain = create_tree();
+        chain.set_joint_positions(&Q).unwrap();
+        chain.set_joint_velocities(&DQ).unwrap();
+        chain.update_velocities();
+        let node = chain.find("fixed3").unwrap();
+        let trans = node.world_transform().unwrap();
+        let world = node.world_velocity().unwrap();
+        let body = node.body_velocity().unwrap();
+        assert!((trans.rotation * body.linear - world.translation).norm() < 1e-10);
+        assert!((trans.rotation * body.angular - world.rotation).norm() < 1e-10);
+
+        // the velocity of the point equals to the velocity of a fixed child node at the point
+        let point = Vector3::new(0.2, 0.0, 0.1);
+        let parent = chain.find("rot2").unwrap();
+        let point_velocity = parent.point_velocity(&point).unwrap();
+        assert!((point_velocity.translation - world.translation).norm() < 1e-10);
+    }
+}